@@ -4,7 +4,12 @@ use axum::{Router, response::Html, routing::get};
 async fn main() {
     let frontend = spaxum::load!("Example")
         .set_title("Example Site")
-        .set_process_html(|html: String| html.replace("Example Site", "Example Page"));
+        .set_process_html(|html: String| {
+            Box::pin(async move { html.replace("Example Site", "Example Page") })
+        });
+
+    // stop the esbuild dev server when the server shuts down
+    let shutdown = frontend.shutdown_handle();
 
     let app = Router::new()
         .merge(frontend.router())
@@ -16,7 +21,13 @@ async fn main() {
         .unwrap();
 
     println!("listening on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            let _ = tokio::signal::ctrl_c().await;
+            shutdown.shutdown().await;
+        })
+        .await
+        .unwrap();
 }
 
 async fn handler() -> Html<&'static str> {
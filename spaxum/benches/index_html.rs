@@ -0,0 +1,75 @@
+//! Time serving the index.html with and without a per request hook, run with `cargo bench`
+//! Without the hook the index.html is rendered once, the hook should only add to the time of its own requests
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    extract::Request,
+};
+use spaxum::{EntryFiles, Spaxum};
+use std::time::{Duration, Instant};
+use tower_service::Service;
+
+/// Number of timed requests per router
+const REQUESTS: u32 = 100_000;
+
+/// Number of requests before timing, the index.html is rendered on the first request
+const WARMUP: u32 = 1_000;
+
+fn spaxum() -> Spaxum {
+    let entry_files = EntryFiles {
+        js: "index-5QHS2WJN.js".to_string(),
+        css: "index-G2ZSN5UJ.css".to_string(),
+        js_sri: String::new(),
+        css_sri: String::new(),
+        additional: Vec::new(),
+        module: false,
+    };
+
+    Spaxum::new("Benchmark", &[], entry_files)
+}
+
+async fn request(router: &mut Router) {
+    let request = Request::get("/")
+        .body(Body::empty())
+        .expect("invalid request");
+    let response = match router.call(request).await {
+        Ok(response) => response,
+        Err(e) => match e {},
+    };
+
+    to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("unable to read the index.html");
+}
+
+/// Average time per request
+async fn time_requests(mut router: Router) -> Duration {
+    for _ in 0..WARMUP {
+        request(&mut router).await;
+    }
+
+    let start = Instant::now();
+    for _ in 0..REQUESTS {
+        request(&mut router).await;
+    }
+
+    start.elapsed() / REQUESTS
+}
+
+#[tokio::main]
+async fn main() {
+    let benchmarks = [
+        ("static", spaxum().router()),
+        (
+            "per request hook",
+            spaxum()
+                .set_process_html_per_request(|html, _| html)
+                .router(),
+        ),
+        ("csp nonce", spaxum().with_csp_nonce().router()),
+    ];
+
+    for (name, router) in benchmarks {
+        println!("{name:>16}: {:?} per request", time_requests(router).await);
+    }
+}
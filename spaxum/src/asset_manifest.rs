@@ -0,0 +1,85 @@
+use memory_serve::Asset;
+use serde::Serialize;
+use std::path::Path;
+
+/// Static asset entry of the asset manifest
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct AssetInfo {
+    path: String,
+    size: usize,
+    content_type: String,
+}
+
+/// List the assets embedded in the binary, the size is the size of the embedded (compressed) data
+pub(crate) fn memory_assets(assets: &[Asset], static_prefix: &str) -> Vec<AssetInfo> {
+    assets
+        .iter()
+        .map(|asset| AssetInfo {
+            path: format!("{static_prefix}{}", asset.route),
+            size: match asset.bytes {
+                Some(bytes) => bytes.len(),
+                // assets that are not embedded are read from disk
+                None => std::fs::metadata(asset.path)
+                    .map(|metadata| metadata.len() as usize)
+                    .unwrap_or_default(),
+            },
+            content_type: asset.content_type.to_string(),
+        })
+        .collect()
+}
+
+/// List the assets in the esbuild dist directory, as served by the esbuild dev server
+pub(crate) fn dist_assets(dist_dir: &Path, static_prefix: &str) -> Vec<AssetInfo> {
+    let mut assets = Vec::new();
+    collect_dist_assets(dist_dir, static_prefix, &mut assets);
+    assets.sort_by(|a, b| a.path.cmp(&b.path));
+
+    assets
+}
+
+/// Recursively collect the files in a directory of the dist directory
+fn collect_dist_assets(dir: &Path, route: &str, assets: &mut Vec<AssetInfo>) {
+    // the dist directory does not exist until esbuild finished its first build
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let route = format!("{route}/{}", entry.file_name().to_string_lossy());
+
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            collect_dist_assets(&path, &route, assets);
+        } else {
+            assets.push(AssetInfo {
+                path: route,
+                size: metadata.len() as usize,
+                content_type: content_type(&path).to_string(),
+            });
+        }
+    }
+}
+
+/// Content type of an esbuild output file, based on the file extension
+pub(crate) fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("js") => "text/javascript",
+        Some("css") => "text/css",
+        Some("map" | "json") => "application/json",
+        Some("html") => "text/html",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("wasm") => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
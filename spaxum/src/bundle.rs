@@ -0,0 +1,1691 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384};
+use std::{
+    collections::HashMap,
+    env,
+    io::BufRead,
+    path::{Path, PathBuf},
+    process::{Stdio, exit},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use crate::{
+    ESBUILD_OPTIONS, Esbuild, SERVICE_WORKER_FILE, STATIC_PREFIX, asset_manifest::content_type,
+    compress, normalize_base_href, probe_esbuild,
+};
+
+/// Esbuild manifest output structure
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Output {
+    bytes: usize,
+    css_bundle: Option<String>,
+    entry_point: Option<String>,
+    #[serde(default)]
+    inputs: HashMap<String, OutputInput>,
+}
+
+/// Esbuild manifest input of an output file
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputInput {
+    bytes_in_output: usize,
+}
+
+/// Esbuild manifest structure
+#[derive(Debug, Deserialize, Serialize)]
+struct Manifest {
+    outputs: HashMap<String, Output>,
+}
+
+impl Manifest {
+    /// Read and parse the esbuild manifest file
+    fn read(manifest_file: &str) -> Self {
+        let manifest_str =
+            std::fs::read_to_string(manifest_file).expect("Unable to read manifest file.");
+
+        serde_json::from_str(&manifest_str).expect("Unmable to parse manifest file.")
+    }
+
+    /// Get the output file names (js, css) for a canonical entrypoint
+    /// The entrypoints in the manifest are relative to the working directory of esbuild
+    fn entry_files(&self, entrypoint: &Path, working_dir: &Path) -> Option<(String, String)> {
+        for (name, output) in self.outputs.iter() {
+            if let Some(js) = output.entry_point.as_ref()
+                && working_dir
+                    .join(js)
+                    .canonicalize()
+                    .is_ok_and(|path| path == entrypoint)
+            {
+                return Some((
+                    Path::new(name)
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    output
+                        .css_bundle
+                        .as_ref()
+                        .and_then(|f| {
+                            Some(Path::new(&f).file_name()?.to_string_lossy().to_string())
+                        })
+                        .unwrap_or_default(),
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+/// Error macro for build scripts
+macro_rules! error {
+    ($s:expr) => {
+        println!("cargo::error={}", $s);
+        exit(1);
+    };
+
+    ($s:expr, $($v:tt)*) => {
+        println!("cargo::error={}", format!($s, $($v)*));
+        exit(1);
+    };
+}
+
+/// Log messages to cargo
+fn log(msg: &str) {
+    if std::env::var("SPAXUM_QUIET") != Ok("1".to_string()) {
+        println!("cargo::warning={}", msg);
+    }
+}
+
+/// Esbuild defines (`name=value`) collected by the define helpers, passed to esbuild when bundling
+static DEFINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Add an esbuild define, replacing the global identifier `name` by `value` in the bundle
+fn add_define(name: &str, value: &str) {
+    DEFINES
+        .lock()
+        .expect("defines lock poisoned")
+        .push(format!("{name}={value}"));
+}
+
+/// Define a global constant in the bundle from a Cargo feature of the crate being built
+/// `js_name` is `true` if the feature is enabled and `false` otherwise, multiple calls accumulate
+/// Call before bundling, the defines are passed to the release build and to the esbuild dev server
+pub fn define_from_cargo_feature(feature: &str, js_name: &str) {
+    let feature_var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+    let enabled = env::var_os(feature_var).is_some();
+
+    add_define(js_name, if enabled { "true" } else { "false" });
+}
+
+/// Define a global string constant in the bundle from an environment variable read at build time
+/// A missing variable results in a warning and an empty string, multiple calls accumulate
+/// Call before bundling, the defines are passed to the release build and to the esbuild dev server
+pub fn inject_env_var(rust_env_key: &str, js_global: &str) {
+    println!("cargo::rerun-if-env-changed={rust_env_key}");
+
+    let value = env::var(rust_env_key).unwrap_or_else(|_| {
+        println!("cargo::warning=Environment variable {rust_env_key} is not set, defining {js_global} as an empty string");
+        String::new()
+    });
+
+    add_define(
+        js_global,
+        &serde_json::to_string(&value).unwrap_or_default(),
+    );
+}
+
+/// Define the version of the crate being built as `__APP_VERSION__` in the bundle
+pub fn inject_pkg_version() {
+    inject_env_var("CARGO_PKG_VERSION", "__APP_VERSION__");
+}
+
+/// Path the assets are served from, set by `set_static_path`
+static STATIC_PATH: Mutex<Option<String>> = Mutex::new(None);
+
+/// Set the path the assets are served from, defaults to "/static", call before bundling
+/// The path is the esbuild public path of the release build, and is passed to the `load!` macro,
+/// which serves the assets from it in development and in release builds
+pub fn set_static_path(path: &str) {
+    let path = path.trim_matches('/');
+
+    if path.is_empty() {
+        error!("The static path can not be empty or \"/\"");
+    }
+
+    *STATIC_PATH.lock().expect("static path lock poisoned") = Some(format!("/{path}"));
+}
+
+/// Get the path the assets are served from
+fn static_path() -> String {
+    STATIC_PATH
+        .lock()
+        .expect("static path lock poisoned")
+        .clone()
+        .unwrap_or_else(|| STATIC_PREFIX.to_string())
+}
+
+/// URL the assets are loaded from in release builds, set by `set_public_path`
+static PUBLIC_PATH: Mutex<Option<String>> = Mutex::new(None);
+
+/// Set the URL the assets of release builds are loaded from, e.g. `https://cdn.example.com/assets`
+/// Defaults to the static path, the assets are still served from the static path, e.g. as origin of the CDN
+/// The URL is the esbuild public path and is passed to the `load!` macro for the index.html, call before bundling
+pub fn set_public_path(path: &str) {
+    let path = path.trim_end_matches('/');
+
+    if path.is_empty() {
+        error!("The public path can not be empty or \"/\"");
+    }
+
+    *PUBLIC_PATH.lock().expect("public path lock poisoned") = Some(path.to_string());
+}
+
+/// Get the URL the assets are loaded from in release builds
+/// Without a public path the static path is prefixed by the `SPAXUM_BASE_HREF` environment variable
+fn public_path() -> String {
+    PUBLIC_PATH
+        .lock()
+        .expect("public path lock poisoned")
+        .clone()
+        .unwrap_or_else(|| match base_href() {
+            Some(base_href) => format!("{}{}", base_href.trim_end_matches('/'), static_path()),
+            None => static_path(),
+        })
+}
+
+/// Path the application is served from behind a reverse proxy, see `Spaxum::set_base_href`
+fn base_href() -> Option<String> {
+    println!("cargo::rerun-if-env-changed=SPAXUM_BASE_HREF");
+
+    normalize_base_href(&env::var("SPAXUM_BASE_HREF").ok()?)
+}
+
+/// Icon files of the Progressive Web App, copied to the dist directory when bundling
+static PWA_ICONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Include the icon files of the web app manifest in the assets, see `Spaxum::with_pwa_manifest`
+/// The icons are served from the static prefix by their file name, multiple calls accumulate
+/// Call before bundling, the icons are served in development and embedded in release builds
+pub fn include_pwa_icons(icons: &[&str]) {
+    PWA_ICONS
+        .lock()
+        .expect("pwa icons lock poisoned")
+        .extend(icons.iter().map(|icon| icon.to_string()));
+}
+
+/// Copy a favicon next to the entrypoint to the assets, see `include_favicon`
+static FAVICON: AtomicBool = AtomicBool::new(false);
+
+/// Favicon files looked up next to the entrypoint, in order of preference
+const FAVICON_FILES: [&str; 3] = ["favicon.svg", "favicon.png", "favicon.ico"];
+
+/// Include the `favicon.svg`, `favicon.png` or `favicon.ico` next to the entrypoint in the assets
+/// The `load!` macro serves it at `/favicon.ico` and adds a `<link rel="icon">` tag to the index.html
+/// Call before bundling
+pub fn include_favicon() {
+    FAVICON.store(true, Ordering::Relaxed);
+}
+
+/// Copy the favicon next to the entrypoint to the dist directory, and pass its file name to the `load!` macro
+fn copy_favicon(entrypoint: &Path, dist_dir: &Path) {
+    if !FAVICON.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let Some(source_dir) = entrypoint.parent() else {
+        return;
+    };
+
+    let Some(favicon) = FAVICON_FILES
+        .iter()
+        .map(|file_name| source_dir.join(file_name))
+        .find(|path| path.exists())
+    else {
+        log(&format!(
+            "No favicon found in {}, expected one of {}",
+            source_dir.to_string_lossy(),
+            FAVICON_FILES.join(", ")
+        ));
+        return;
+    };
+
+    println!("cargo::rerun-if-changed={}", favicon.to_string_lossy());
+
+    if let Err(e) = std::fs::create_dir_all(dist_dir) {
+        error!(
+            "Unable to create dist directory {}: {}",
+            dist_dir.to_string_lossy(),
+            e
+        );
+    }
+
+    let Some(file_name) = favicon.file_name() else {
+        return;
+    };
+
+    if let Err(e) = std::fs::copy(&favicon, dist_dir.join(file_name)) {
+        error!(
+            "Unable to copy favicon {}: {}",
+            favicon.to_string_lossy(),
+            e
+        );
+    }
+
+    println!(
+        "cargo::rustc-env=SPAXUM_FAVICON={}",
+        file_name.to_string_lossy()
+    );
+}
+
+/// Copy the icons of the web app manifest to the dist directory
+fn copy_pwa_icons(dist_dir: &Path) {
+    let icons = PWA_ICONS.lock().expect("pwa icons lock poisoned").clone();
+
+    if icons.is_empty() {
+        return;
+    }
+
+    if let Err(e) = std::fs::create_dir_all(dist_dir) {
+        error!(
+            "Unable to create dist directory {}: {}",
+            dist_dir.to_string_lossy(),
+            e
+        );
+    }
+
+    for icon in icons {
+        let icon = Path::new(&icon);
+        println!("cargo::rerun-if-changed={}", icon.to_string_lossy());
+
+        let Some(file_name) = icon.file_name() else {
+            error!("Invalid icon path: {}", icon.to_string_lossy());
+        };
+
+        if let Err(e) = std::fs::copy(icon, dist_dir.join(file_name)) {
+            error!("Unable to copy icon {}: {}", icon.to_string_lossy(), e);
+        }
+    }
+}
+
+/// Entrypoint of the service worker, bundled separately to an unhashed file
+static SERVICE_WORKER: Mutex<Option<String>> = Mutex::new(None);
+
+/// Bundle a service worker entrypoint to `sw.js`, see `Spaxum::with_service_worker`
+/// The output is not hashed, since a service worker is registered by its path
+/// Call before bundling, in development the service worker is bundled by the build script, not watched
+pub fn bundle_service_worker(entrypoint: &str) {
+    *SERVICE_WORKER.lock().expect("service worker lock poisoned") = Some(entrypoint.to_string());
+}
+
+/// Bundle the service worker to the dist directory, if any, minified in release builds
+fn bundle_service_worker_entry(dist_dir: &Path, defines: &[String], minify: bool) {
+    let Some(entrypoint) = SERVICE_WORKER
+        .lock()
+        .expect("service worker lock poisoned")
+        .clone()
+    else {
+        return;
+    };
+
+    let Ok(entrypoint) = Path::new(&entrypoint).canonicalize() else {
+        error!("{} not found!", entrypoint);
+    };
+    let entrypoint_str = entrypoint.to_string_lossy().to_string();
+
+    if let Some(source_dir) = entrypoint.parent() {
+        println!("cargo::rerun-if-changed={}", source_dir.to_string_lossy());
+    }
+
+    let (esbuild, _) = match probe_esbuild(entrypoint.parent()) {
+        Ok(esbuild) => esbuild,
+        Err(e) => {
+            error!(e);
+        }
+    };
+
+    let mut args = vec![
+        entrypoint_str.clone(),
+        "--bundle".to_string(),
+        format!(
+            "--outfile={}",
+            dist_dir.join(SERVICE_WORKER_FILE).to_string_lossy()
+        ),
+    ];
+    if minify {
+        args.push("--minify".to_string());
+    }
+    args.extend(ESBUILD_OPTIONS.iter().map(|option| option.to_string()));
+    args.push(format!("--color={}", COLOR.load(Ordering::Relaxed)));
+    if let Some(log_level) = *LOG_LEVEL.lock().expect("log level lock poisoned") {
+        args.push(format!("--log-level={}", log_level.as_str()));
+    }
+    args.extend(defines.iter().map(|define| format!("--define:{define}")));
+
+    log(&format!(
+        "Bundling service worker {entrypoint_str} using esbuild..."
+    ));
+
+    let mut command = esbuild.command();
+    command.args(&args);
+
+    run_bundler(command, &entrypoint_str);
+}
+
+/// Get the short hash of the current git commit, `unknown` if it cannot be determined
+fn git_hash() -> String {
+    git_output(&["rev-parse", "--short", "HEAD"]).unwrap_or_else(|| {
+        log("Unable to determine the git commit hash, using \"unknown\"");
+        "unknown".to_string()
+    })
+}
+
+/// Run a git command in the working directory, `None` if it fails
+fn git_output(args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Files that change when a commit is made or checked out: `HEAD`, the branch it points to and the packed refs
+/// A branch that is not a loose file yet is watched through its directory, cargo always reruns for missing files
+fn git_head_files() -> Vec<PathBuf> {
+    let (Some(git_dir), Some(common_dir)) = (
+        git_output(&["rev-parse", "--git-dir"]),
+        git_output(&["rev-parse", "--git-common-dir"]),
+    ) else {
+        return Vec::new();
+    };
+    let (git_dir, common_dir) = (PathBuf::from(git_dir), PathBuf::from(common_dir));
+
+    let mut files = vec![git_dir.join("HEAD"), common_dir.join("packed-refs")];
+
+    // a detached HEAD contains the commit hash itself
+    if let Some(head_ref) = git_output(&["symbolic-ref", "-q", "HEAD"]) {
+        let ref_file = common_dir.join(head_ref);
+
+        match ref_file.parent() {
+            Some(ref_dir) if !ref_file.exists() => files.push(ref_dir.to_path_buf()),
+            _ => files.push(ref_file),
+        }
+    }
+
+    files.retain(|file| file.exists());
+
+    files
+}
+
+/// File name of the cache key of the esbuild output, in `OUT_DIR`
+const CACHE_KEY_FILE: &str = "spaxum_cache_key";
+
+/// Hash the files in a source directory recursively, in a stable order
+fn hash_source_dir(dir: &Path, hasher: &mut Sha256) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut paths = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            hash_source_dir(&path, hasher);
+        } else if let Ok(contents) = std::fs::read(&path) {
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update(Sha256::digest(&contents));
+        }
+    }
+}
+
+/// Compute the cache key of the esbuild output from the sources, the esbuild version and the arguments
+fn cache_key(
+    source_dirs: &[&Path],
+    esbuild_version: &str,
+    args: &[String],
+    inline_threshold: Option<usize>,
+) -> String {
+    let mut hasher = Sha256::new();
+
+    for source_dir in source_dirs {
+        hash_source_dir(source_dir, &mut hasher);
+    }
+
+    hasher.update(esbuild_version.as_bytes());
+
+    for arg in args {
+        hasher.update(arg.as_bytes());
+        hasher.update([0]);
+    }
+
+    hasher.update(format!("{inline_threshold:?}").as_bytes());
+
+    STANDARD.encode(hasher.finalize())
+}
+
+/// File name to write asset metadata to
+const ASSET_FILE: &str = "spaxum.rs";
+
+/// Write the asset metadata to a file
+fn write_asset_file(out_dir: &Path, code: &str) {
+    let target = out_dir.join(ASSET_FILE);
+    match std::fs::write(&target, code) {
+        Ok(_) => {}
+        Err(e) => {
+            error!(
+                "Unable to write asset file: {} {e:?}",
+                target.to_string_lossy()
+            );
+        }
+    }
+}
+
+/// Compute the base64 encoded SHA-384 digest of an output file, used for subresource integrity
+fn sri_digest(dist_dir: &Path, file_name: &str) -> String {
+    if file_name.is_empty() {
+        return String::new();
+    }
+
+    let path = dist_dir.join(file_name);
+    let Ok(contents) = std::fs::read(&path) else {
+        error!("Unable to read output file: {}", path.to_string_lossy());
+    };
+
+    STANDARD.encode(Sha384::digest(&contents))
+}
+
+/// Output format of the esbuild bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BundleFormat {
+    /// Immediately-invoked function expression, the esbuild default for browsers
+    #[default]
+    Iife,
+    /// ES modules, with code splitting into shared chunks
+    Esm,
+    /// CommonJS modules
+    Cjs,
+}
+
+impl BundleFormat {
+    /// Esbuild arguments for the output format, writing the output to the dist directory
+    fn esbuild_args(self, dist_dir: &str) -> Vec<String> {
+        match self {
+            BundleFormat::Iife => vec![format!("--outfile={dist_dir}/index.js")],
+            BundleFormat::Esm => vec![
+                format!("--outdir={dist_dir}"),
+                "--format=esm".to_string(),
+                "--splitting".to_string(),
+            ],
+            BundleFormat::Cjs => vec![
+                format!("--outfile={dist_dir}/index.js"),
+                "--format=cjs".to_string(),
+            ],
+        }
+    }
+}
+
+/// Esbuild log level, esbuild logs at the info level by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum LogLevel {
+    /// Everything, including internal details
+    Verbose,
+    /// Debug details, like the files esbuild reads
+    Debug,
+    /// Warnings, errors and a summary of every build
+    #[default]
+    Info,
+    /// Warnings and errors
+    Warning,
+    /// Errors only
+    Error,
+    /// Nothing
+    Silent,
+}
+
+impl LogLevel {
+    /// Value of the esbuild `--log-level` argument
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Verbose => "verbose",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+            LogLevel::Silent => "silent",
+        }
+    }
+}
+
+/// Esbuild log level of the release build, set by `set_esbuild_log_level`
+static LOG_LEVEL: Mutex<Option<LogLevel>> = Mutex::new(None);
+
+/// Colored esbuild output of the release build, set by `set_esbuild_color`
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Set the esbuild log level of the release build, call before bundling
+pub fn set_esbuild_log_level(level: LogLevel) {
+    *LOG_LEVEL.lock().expect("log level lock poisoned") = Some(level);
+}
+
+/// Enable or disable colored esbuild output of the release build, disabled by default, call before bundling
+pub fn set_esbuild_color(color: bool) {
+    COLOR.store(color, Ordering::Relaxed);
+}
+
+/// Maximum size of the bundle output files
+#[derive(Debug, Clone, Copy)]
+enum SizeLimit {
+    /// Emit a cargo warning for every output file that exceeds the limit
+    Warn(usize),
+    /// Abort the build if an output file exceeds the limit
+    Error(usize),
+}
+
+impl SizeLimit {
+    /// Check the size of every output file in the manifest
+    fn check(self, manifest: &Manifest) {
+        let (SizeLimit::Warn(limit_bytes) | SizeLimit::Error(limit_bytes)) = self;
+
+        let mut outputs = manifest
+            .outputs
+            .iter()
+            .filter(|(_, output)| output.bytes > limit_bytes)
+            .collect::<Vec<_>>();
+        outputs.sort_by_key(|(name, _)| name.as_str());
+
+        for (name, output) in outputs {
+            let name = Path::new(name)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            let bytes = output.bytes;
+
+            match self {
+                SizeLimit::Warn(_) => {
+                    println!(
+                        "cargo::warning=bundle output {name} is {bytes}B, exceeding limit {limit_bytes}B"
+                    );
+                }
+                SizeLimit::Error(_) => {
+                    error!(
+                        "bundle output {} is {}B, exceeding limit {}B",
+                        name, bytes, limit_bytes
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Number of modules in the largest modules list of the bundle analysis
+const ANALYSIS_LARGEST_MODULES: usize = 20;
+
+/// Size of a module in the bundle analysis
+#[derive(Debug, Serialize)]
+struct ModuleSize {
+    path: String,
+    bytes: usize,
+    /// Share of the total JS and CSS bundle size, between 0 and 1
+    share: f64,
+}
+
+/// Human-readable summary of the bundle analysis
+#[derive(Debug, Serialize)]
+struct BundleAnalysis {
+    js_bytes: usize,
+    css_bytes: usize,
+    total_bytes: usize,
+    /// Modules sorted by size, largest first
+    modules: Vec<ModuleSize>,
+    largest_modules: Vec<ModuleSize>,
+}
+
+impl BundleAnalysis {
+    /// Summarize the module sizes in the manifest, a module may be part of multiple output files
+    fn new(manifest: &Manifest) -> Self {
+        let mut js_bytes = 0;
+        let mut css_bytes = 0;
+        let mut module_bytes: HashMap<&str, usize> = HashMap::new();
+
+        for (name, output) in &manifest.outputs {
+            if name.ends_with(".js") {
+                js_bytes += output.bytes;
+            } else if name.ends_with(".css") {
+                css_bytes += output.bytes;
+            } else {
+                continue;
+            }
+
+            for (path, input) in &output.inputs {
+                *module_bytes.entry(path).or_default() += input.bytes_in_output;
+            }
+        }
+
+        let total_bytes = js_bytes + css_bytes;
+        let module_size = |(path, bytes): (&str, usize)| ModuleSize {
+            path: path.to_string(),
+            bytes,
+            share: bytes as f64 / total_bytes.max(1) as f64,
+        };
+
+        let mut sizes = module_bytes.into_iter().collect::<Vec<_>>();
+        sizes.sort_by(|(a_path, a_bytes), (b_path, b_bytes)| {
+            b_bytes.cmp(a_bytes).then(a_path.cmp(b_path))
+        });
+
+        BundleAnalysis {
+            js_bytes,
+            css_bytes,
+            total_bytes,
+            largest_modules: sizes
+                .iter()
+                .take(ANALYSIS_LARGEST_MODULES)
+                .copied()
+                .map(module_size)
+                .collect(),
+            modules: sizes.into_iter().map(module_size).collect(),
+        }
+    }
+
+    /// Copy the metafile to `report_path` and write the summary next to it, as `<report>.summary.json`
+    fn write(&self, manifest_file: &Path, report_path: &Path) {
+        if let Some(parent) = report_path.parent()
+            && !parent.as_os_str().is_empty()
+            && let Err(e) = std::fs::create_dir_all(parent)
+        {
+            error!(
+                "Unable to create the bundle analysis directory {}: {}",
+                parent.to_string_lossy(),
+                e
+            );
+        }
+
+        if let Err(e) = std::fs::copy(manifest_file, report_path) {
+            error!(
+                "Unable to write the bundle analysis {}: {}",
+                report_path.to_string_lossy(),
+                e
+            );
+        }
+
+        let summary_path = report_path.with_extension("summary.json");
+        let summary = serde_json::to_string_pretty(self).unwrap_or_default();
+
+        if let Err(e) = std::fs::write(&summary_path, summary) {
+            error!(
+                "Unable to write the bundle analysis summary {}: {}",
+                summary_path.to_string_lossy(),
+                e
+            );
+        }
+
+        log(&format!(
+            "Bundle size: JS {}B, CSS {}B, analysis written to {}",
+            self.js_bytes,
+            self.css_bytes,
+            report_path.to_string_lossy()
+        ));
+    }
+}
+
+/// Options of a release build, set by the bundle functions
+#[derive(Debug, Default)]
+struct BundleOptions<'a> {
+    format: BundleFormat,
+    build_args: &'a [&'a str],
+    size_limit: Option<SizeLimit>,
+    inline_threshold: Option<usize>,
+    watch_dirs: &'a [&'a str],
+    plugin_script: Option<&'a str>,
+    report_path: Option<&'a str>,
+    entry_names: Option<&'a str>,
+}
+
+/// Collect the files in a directory of the dist directory, recursively
+pub(crate) fn output_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            output_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// Replace `url(<static path>/...)` references in the CSS output by data URIs, for files smaller than the threshold
+/// Inlined files that are not referenced by any other output file are removed from the dist directory,
+/// so they are not embedded in the binary
+fn inline_small_assets(dist_dir: &Path, threshold_bytes: usize) {
+    let mut files = Vec::new();
+    output_files(dist_dir, &mut files);
+
+    let static_prefix = format!("{}/", public_path());
+    let mut inlined: Vec<PathBuf> = Vec::new();
+
+    for css_file in files
+        .iter()
+        .filter(|file| file.extension().is_some_and(|extension| extension == "css"))
+    {
+        let Ok(css) = std::fs::read_to_string(css_file) else {
+            error!("Unable to read output file: {}", css_file.to_string_lossy());
+        };
+
+        let mut output = String::with_capacity(css.len());
+        let mut rest = css.as_str();
+
+        while let Some(start) = rest.find("url(") {
+            let (before, after) = rest.split_at(start + 4);
+            output.push_str(before);
+
+            let Some(end) = after.find(')') else {
+                break;
+            };
+
+            let url = after[..end].trim().trim_matches(|c| c == '"' || c == '\'');
+            let asset = url
+                .strip_prefix(&static_prefix)
+                .filter(|route| !route.contains(['?', '#']))
+                .map(|route| dist_dir.join(route))
+                .filter(|asset| {
+                    asset
+                        .metadata()
+                        .is_ok_and(|metadata| (metadata.len() as usize) < threshold_bytes)
+                });
+
+            match asset.as_deref().map(|asset| (asset, std::fs::read(asset))) {
+                Some((asset, Ok(contents))) => {
+                    output.push_str(&format!(
+                        "\"data:{};base64,{}\"",
+                        content_type(asset),
+                        STANDARD.encode(contents)
+                    ));
+
+                    if !inlined.iter().any(|inlined| inlined == asset) {
+                        inlined.push(asset.to_path_buf());
+                    }
+                }
+                _ => output.push_str(&after[..end]),
+            }
+
+            rest = &after[end..];
+        }
+
+        output.push_str(rest);
+
+        if output != css
+            && let Err(e) = std::fs::write(css_file, output)
+        {
+            error!(
+                "Unable to write output file: {} {:?}",
+                css_file.to_string_lossy(),
+                e
+            );
+        }
+    }
+
+    for asset in inlined {
+        let Some(file_name) = asset
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+        else {
+            continue;
+        };
+
+        // the asset may still be referenced, e.g. by a javascript import
+        let referenced = files.iter().any(|file| {
+            file != &asset
+                && std::fs::read_to_string(file).is_ok_and(|contents| contents.contains(&file_name))
+        });
+
+        if !referenced {
+            log(&format!("Inlined {file_name} as a data URI"));
+            let _ = std::fs::remove_file(&asset);
+        }
+    }
+}
+
+/// Bundle the assets using release compilation with esbuild
+/// Pass the entrypoint to the runtime for debug builds
+pub fn bundle(entrypoint: &str) {
+    bundle_with_args(entrypoint, &[]);
+}
+
+/// Bundle the assets using release compilation with esbuild
+/// Pass the entrypoint to the runtime for debug builds
+/// Optionally pass additional arguments to esbuild
+pub fn bundle_with_args(entrypoint: &str, build_args: &[&str]) {
+    bundle_entrypoints(
+        &[entrypoint],
+        BundleOptions {
+            build_args,
+            ..Default::default()
+        },
+    );
+}
+
+/// Bundle the assets using release compilation with esbuild, in the given output format
+/// ES modules are loaded with `<script type="module">` and split into shared chunks
+/// Optionally pass additional arguments to esbuild
+pub fn bundle_with_format(entrypoint: &str, format: BundleFormat, build_args: &[&str]) {
+    bundle_entrypoints(
+        &[entrypoint],
+        BundleOptions {
+            format,
+            build_args,
+            ..Default::default()
+        },
+    );
+}
+
+/// Bundle the assets using release compilation with esbuild, with external source maps
+/// The `.map` files are embedded and served next to the bundle, without a `sourceMappingURL` comment in
+/// the bundle, so the source maps are only loaded when they are added in the browser devtools
+pub fn bundle_with_source_maps(entrypoint: &str) {
+    bundle_with_args(entrypoint, &["--sourcemap=external"]);
+}
+
+/// Bundle the assets using release compilation with esbuild, for the given browser targets
+/// Targets follow the esbuild target syntax, e.g. `["chrome100", "firefox110", "safari16"]`
+/// Optionally pass additional arguments to esbuild
+pub fn bundle_with_targets(entrypoint: &str, targets: &[&str], build_args: &[&str]) {
+    let target = format!("--target={}", targets.join(","));
+
+    let mut args = vec![target.as_str()];
+    args.extend_from_slice(build_args);
+
+    bundle_with_args(entrypoint, &args);
+}
+
+/// Bundle the assets using release compilation with esbuild, without the external packages, e.g. `["react"]`
+/// The imports of external packages are kept, e.g. to load them from a CDN with an import map
+/// Optionally pass additional arguments to esbuild
+pub fn bundle_with_externals(entrypoint: &str, externals: &[&str], build_args: &[&str]) {
+    let externals = externals
+        .iter()
+        .map(|external| format!("--external:{external}"))
+        .collect::<Vec<String>>();
+
+    let mut args = externals.iter().map(String::as_str).collect::<Vec<&str>>();
+    args.extend_from_slice(build_args);
+
+    bundle_with_args(entrypoint, &args);
+}
+
+/// Bundle the assets using release compilation with esbuild, with the current git commit hash
+/// The short hash is available as the global constant `__GIT_HASH__` in the bundle
+/// and as the `SPAXUM_GIT_HASH` environment variable at compile time
+pub fn bundle_with_git_hash(entrypoint: &str) {
+    let hash = git_hash();
+
+    // the hash changes with every commit, also when the frontend sources do not
+    for file in git_head_files() {
+        println!("cargo::rerun-if-changed={}", file.to_string_lossy());
+    }
+
+    println!("cargo::rustc-env=SPAXUM_GIT_HASH={hash}");
+    add_define(
+        "__GIT_HASH__",
+        &serde_json::to_string(&hash).unwrap_or_default(),
+    );
+
+    bundle(entrypoint);
+}
+
+/// Bundle multiple entrypoints using release compilation with esbuild
+/// The entrypoints are bundled as separate ES modules that share code through chunks
+/// The first entrypoint is used for `%SCRIPT%` and `%STYLESHEET%`, the others are added to the HTML
+/// All entrypoints are passed to the runtime for debug builds
+pub fn bundle_multiple(entrypoints: &[&str], build_args: &[&str]) {
+    bundle_entrypoints(
+        entrypoints,
+        BundleOptions {
+            format: BundleFormat::Esm,
+            build_args,
+            ..Default::default()
+        },
+    );
+}
+
+/// Bundle the assets using release compilation with esbuild
+/// Emit a cargo warning for every output file larger than `limit_bytes`
+/// Optionally pass additional arguments to esbuild
+pub fn bundle_with_size_limit(entrypoint: &str, limit_bytes: usize, build_args: &[&str]) {
+    bundle_entrypoints(
+        &[entrypoint],
+        BundleOptions {
+            build_args,
+            size_limit: Some(SizeLimit::Warn(limit_bytes)),
+            ..Default::default()
+        },
+    );
+}
+
+/// Bundle the assets using release compilation with esbuild, and write a bundle analysis
+/// The esbuild metafile is copied to `report_path`, a summary with the size and share of every module
+/// and the largest modules is written next to it as `<report>.summary.json`
+/// The report is only written in release builds
+/// Optionally pass additional arguments to esbuild
+pub fn bundle_with_analysis(entrypoint: &str, report_path: &str, build_args: &[&str]) {
+    bundle_entrypoints(
+        &[entrypoint],
+        BundleOptions {
+            build_args,
+            report_path: Some(report_path),
+            ..Default::default()
+        },
+    );
+}
+
+/// Bundle the assets using release compilation with esbuild, with the pattern of the output file names
+/// The pattern must contain `[name]`, e.g. `[name].[hash]` or `[dir]/[name]`, it defaults to `[name]-[hash]`
+/// Only `[name]-[hash]` names are recognized as content hashed by `set_asset_cache_control`
+/// Optionally pass additional arguments to esbuild
+pub fn bundle_with_entry_names(entrypoint: &str, pattern: &str, build_args: &[&str]) {
+    bundle_entrypoints(
+        &[entrypoint],
+        BundleOptions {
+            build_args,
+            entry_names: Some(pattern),
+            ..Default::default()
+        },
+    );
+}
+
+/// Bundle the assets using release compilation with esbuild
+/// Abort the build if an output file is larger than `limit_bytes`
+/// Optionally pass additional arguments to esbuild
+pub fn bundle_error_on_size_limit(entrypoint: &str, limit_bytes: usize, build_args: &[&str]) {
+    bundle_entrypoints(
+        &[entrypoint],
+        BundleOptions {
+            build_args,
+            size_limit: Some(SizeLimit::Error(limit_bytes)),
+            ..Default::default()
+        },
+    );
+}
+
+/// Bundle the assets using release compilation with esbuild
+/// CSS references to output files smaller than `threshold_bytes`, like small icons and fonts,
+/// are replaced by base64 encoded data URIs, saving a request for each of them
+pub fn bundle_with_inline_threshold(entrypoint: &str, threshold_bytes: usize) {
+    bundle_entrypoints(
+        &[entrypoint],
+        BundleOptions {
+            inline_threshold: Some(threshold_bytes),
+            ..Default::default()
+        },
+    );
+}
+
+/// Run esbuild with the given arguments, writing the output to a clean dist directory
+fn bundle_esbuild(esbuild: &Esbuild, args: &[String], dist_dir: &Path, entrypoints_list: &str) {
+    // Cleanup and ignore if directory is already empty
+    let _ = std::fs::remove_dir_all(dist_dir);
+
+    log(&format!("Bundling {entrypoints_list} using esbuild..."));
+
+    let mut command = esbuild.command();
+    command.args(args);
+
+    run_bundler(command, entrypoints_list);
+}
+
+/// Run esbuild through its JavaScript API with the plugins exported by the plugin script
+/// The esbuild arguments are converted to build options, the output is the same as with the esbuild CLI
+fn bundle_node(
+    args: &[String],
+    plugin_script: &str,
+    out_dir: &Path,
+    dist_dir: &Path,
+    entrypoints_list: &str,
+) {
+    let node_available = std::process::Command::new("node")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+
+    if !node_available {
+        error!(
+            "node not found, it is needed to run the esbuild plugin script, install Node.js and add it to your PATH"
+        );
+    }
+
+    let Ok(plugin_script) = Path::new(plugin_script).canonicalize() else {
+        error!("{} not found!", plugin_script);
+    };
+
+    let (options, metafile) = js_build_options(args);
+    let build_script = out_dir.join("build.mjs");
+    let to_json = |value: &str| serde_json::to_string(value).unwrap_or_default();
+
+    // esbuild and the plugins are resolved from the directory of the plugin script
+    let code = format!(
+        r#"import {{ createRequire }} from "node:module";
+import {{ writeFileSync }} from "node:fs";
+
+const require = createRequire({plugin_script});
+const esbuild = require("esbuild");
+const plugins = require({plugin_script});
+
+const result = await esbuild.build({{ ...{options}, metafile: true, plugins }});
+writeFileSync({metafile}, JSON.stringify(result.metafile));
+"#,
+        plugin_script = to_json(&plugin_script.to_string_lossy()),
+        metafile = to_json(&metafile),
+    );
+
+    if let Err(e) = std::fs::write(&build_script, code) {
+        error!(
+            "Unable to write build script: {} {:?}",
+            build_script.to_string_lossy(),
+            e
+        );
+    }
+
+    // Cleanup and ignore if directory is already empty
+    let _ = std::fs::remove_dir_all(dist_dir);
+
+    log(&format!(
+        "Bundling {entrypoints_list} using esbuild with plugins from {}...",
+        plugin_script.to_string_lossy()
+    ));
+
+    let mut command = std::process::Command::new("node");
+    command.arg(&build_script);
+
+    run_bundler(command, entrypoints_list);
+}
+
+/// Convert esbuild CLI arguments to the build options of the JavaScript API
+/// Returns the options as a JSON object and the path of the metafile, which is written by the build script
+fn js_build_options(args: &[String]) -> (serde_json::Value, String) {
+    use serde_json::{Map, Value};
+
+    let mut options = Map::new();
+    let mut entry_points = Vec::new();
+    let mut metafile = String::new();
+
+    for arg in args {
+        let Some(flag) = arg.strip_prefix("--") else {
+            entry_points.push(Value::from(arg.as_str()));
+            continue;
+        };
+
+        // `--loader:.png=file` and `--define:DEBUG=false` are maps, `--external:react` is a list
+        if let Some((name, entry)) = flag.split_once(':') {
+            let name = camel_case(name);
+
+            match entry.split_once('=') {
+                Some((key, value)) => {
+                    // only the supported map has boolean values, defines are javascript expressions
+                    let value = match (name.as_str(), value) {
+                        ("supported", "true") => Value::Bool(true),
+                        ("supported", "false") => Value::Bool(false),
+                        _ => Value::from(value),
+                    };
+
+                    if let Value::Object(map) = options
+                        .entry(name)
+                        .or_insert_with(|| Value::Object(Map::new()))
+                    {
+                        map.insert(key.to_string(), value);
+                    }
+                }
+                None => {
+                    if let Value::Array(list) = options
+                        .entry(name)
+                        .or_insert_with(|| Value::Array(Vec::new()))
+                    {
+                        list.push(Value::from(entry));
+                    }
+                }
+            }
+
+            continue;
+        }
+
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (flag, None),
+        };
+
+        if name == "metafile" {
+            metafile = value.unwrap_or_default().to_string();
+            continue;
+        }
+
+        let value = match value {
+            None | Some("true") => Value::Bool(true),
+            Some("false") => Value::Bool(false),
+            Some(value)
+                if matches!(
+                    name,
+                    "target" | "resolve-extensions" | "main-fields" | "conditions"
+                ) =>
+            {
+                value.split(',').map(Value::from).collect()
+            }
+            Some(value) => match value.parse::<u64>() {
+                Ok(number) => Value::from(number),
+                Err(_) => Value::from(value),
+            },
+        };
+
+        options.insert(camel_case(name), value);
+    }
+
+    options.insert("entryPoints".to_string(), Value::Array(entry_points));
+
+    (Value::Object(options), metafile)
+}
+
+/// Convert an esbuild CLI flag to the name of the build option, e.g. `public-path` to `publicPath`
+fn camel_case(flag: &str) -> String {
+    let mut parts = flag.split('-');
+    let mut name = parts.next().unwrap_or_default().to_string();
+
+    for part in parts {
+        let mut chars = part.chars();
+
+        if let Some(first) = chars.next() {
+            name.push(first.to_ascii_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+
+    name
+}
+
+/// Run the bundler, forwarding its output to cargo
+fn run_bundler(mut command: std::process::Command, entrypoints_list: &str) {
+    let Ok(mut child) = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    else {
+        error!("esbuild failed to start");
+    };
+
+    if let Some(ref mut stdout) = child.stdout {
+        for line in std::io::BufReader::new(stdout).lines() {
+            let line = line.unwrap();
+            log(&format!("esbuild: {line}"));
+        }
+    }
+
+    if let Some(ref mut stderr) = child.stderr {
+        for line in std::io::BufReader::new(stderr).lines() {
+            let line = line.unwrap();
+            log(&format!("esbuild error: {line}"));
+        }
+    }
+
+    let Ok(status) = child.wait() else {
+        error!("esbuild failed to bundle: {}", entrypoints_list);
+    };
+
+    // Log errors if esbuild fails
+    if !status.success() {
+        error!("esbuild failed to bundle: {}", entrypoints_list);
+    }
+
+    // Log success message
+    log("esbuild completed successfully");
+}
+
+/// Type check the TypeScript sources with `tsc --noEmit`, esbuild does not check types
+/// Pass the path of a `tsconfig.json`, or `None` for the `tsconfig.json` of the crate directory
+/// Type errors are reported as cargo errors and fail the build, call before bundling
+pub fn check_types(tsconfig: Option<&str>) {
+    let project_dir = match tsconfig {
+        Some(tsconfig) => {
+            println!("cargo::rerun-if-changed={tsconfig}");
+            Path::new(tsconfig)
+                .parent()
+                .unwrap_or(Path::new("."))
+                .to_path_buf()
+        }
+        None => PathBuf::from("."),
+    };
+
+    let tsc = node_modules_tsc(&project_dir).unwrap_or_else(|| PathBuf::from("tsc"));
+
+    let mut command = std::process::Command::new(&tsc);
+    command.args(["--noEmit", "--pretty", "false"]);
+    if let Some(tsconfig) = tsconfig {
+        command.args(["-p", tsconfig]);
+    }
+
+    let Ok(output) = command.output() else {
+        error!(
+            "Unable to run {}, install typescript in the node_modules of the frontend",
+            tsc.to_string_lossy()
+        );
+    };
+
+    // tsc reports the type errors on stdout
+    let lines = output
+        .stdout
+        .lines()
+        .chain(output.stderr.lines())
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<String>>();
+
+    if output.status.success() {
+        for line in lines {
+            log(&format!("tsc: {line}"));
+        }
+
+        log("tsc found no type errors");
+        return;
+    }
+
+    for line in &lines {
+        println!("cargo::error=tsc: {line}");
+    }
+
+    error!(
+        "Type checking failed with {} line(s) of errors",
+        lines.len()
+    );
+}
+
+/// Find `node_modules/.bin/tsc` in the directory or its parents
+fn node_modules_tsc(dir: &Path) -> Option<PathBuf> {
+    let name = if cfg!(windows) { "tsc.cmd" } else { "tsc" };
+    let dir = dir.canonicalize().ok()?;
+
+    dir.ancestors()
+        .map(|dir| dir.join("node_modules").join(".bin").join(name))
+        .find(|tsc| tsc.is_file())
+}
+
+/// Bundle the assets using release compilation with esbuild
+/// The build also reruns when a file in one of the extra directories changes, e.g. shared packages in a monorepo
+/// Optionally pass additional arguments to esbuild
+pub fn bundle_watch_dirs(entrypoint: &str, extra_dirs: &[&str], build_args: &[&str]) {
+    bundle_entrypoints(
+        &[entrypoint],
+        BundleOptions {
+            build_args,
+            watch_dirs: extra_dirs,
+            ..Default::default()
+        },
+    );
+}
+
+/// Bundle the assets using release compilation with the esbuild JavaScript API, with esbuild plugins
+/// The plugin script is a CommonJS module that exports an array of esbuild plugins, e.g. for Sass
+/// Requires Node.js, and esbuild installed as a node module next to the plugin script
+/// Optionally pass additional arguments to esbuild, these are converted to build options
+pub fn bundle_with_plugin_script(entrypoint: &str, plugin_script: &str, build_args: &[&str]) {
+    bundle_entrypoints(
+        &[entrypoint],
+        BundleOptions {
+            build_args,
+            plugin_script: Some(plugin_script),
+            ..Default::default()
+        },
+    );
+}
+
+/// Bundle one or more entrypoints with the given options
+fn bundle_entrypoints(entrypoints: &[&str], options: BundleOptions) {
+    let BundleOptions {
+        format,
+        build_args,
+        size_limit,
+        inline_threshold,
+        watch_dirs,
+        plugin_script,
+        report_path,
+        entry_names,
+    } = options;
+
+    // Without the name the output files of the entrypoints cannot be told apart
+    if let Some(entry_names) = entry_names
+        && !entry_names.contains("[name]")
+    {
+        error!(
+            "The entry names pattern {} does not contain [name]",
+            entry_names
+        );
+    }
+
+    // Check if the entrypoints exist
+    let mut canonical_entrypoints: Vec<PathBuf> = Vec::new();
+    for entrypoint in entrypoints {
+        let Ok(entrypoint) = Path::new(entrypoint).canonicalize() else {
+            error!("{} not found!", entrypoint);
+        };
+
+        canonical_entrypoints.push(entrypoint);
+    }
+
+    // Warn about missing watch directories, these are most likely typos
+    let mut extra_dirs: Vec<&Path> = Vec::new();
+    for watch_dir in watch_dirs {
+        let watch_dir = Path::new(watch_dir);
+
+        if watch_dir.exists() {
+            extra_dirs.push(watch_dir);
+        } else {
+            log(&format!(
+                "Watch directory {} not found",
+                watch_dir.to_string_lossy()
+            ));
+        }
+    }
+
+    if canonical_entrypoints.is_empty() {
+        error!("No entrypoint provided!");
+    }
+
+    // Only ES modules can share chunks between entrypoints
+    if canonical_entrypoints.len() > 1 && format != BundleFormat::Esm {
+        error!("Multiple entrypoints can only be bundled as ES modules");
+    }
+
+    // Get the OUT_DIR environment variable, this is where we store compressed assets and asset metadata code
+    let Some(out_dir) = env::var_os("OUT_DIR") else {
+        error!("OUT_DIR not set!");
+    };
+
+    // Create neccesary paths and their string variants
+    let out_dir = Path::new(&out_dir);
+    let dist_dir = out_dir.join("dist");
+    let dist_dir_str = dist_dir.to_string_lossy();
+    let entrypoints_str = canonical_entrypoints
+        .iter()
+        .map(|entrypoint| entrypoint.to_string_lossy().to_string())
+        .collect::<Vec<String>>();
+    let manifest_file = out_dir.join("manifest.json");
+    let manifest_file_str = manifest_file.to_string_lossy();
+
+    // The load! macro serves the assets from the same path as the esbuild public path
+    let static_path = static_path();
+    println!("cargo::rustc-env=SPAXUM_STATIC_PATH={static_path}");
+
+    // The load! macro prefixes the asset URLs in the index.html with the same base href
+    if let Some(base_href) = base_href() {
+        println!("cargo::rustc-env=SPAXUM_BASE_HREF={base_href}");
+    }
+
+    // Esbuild defines collected by the define helpers
+    let defines = DEFINES.lock().expect("defines lock poisoned").clone();
+
+    // Skip bundling in debug mode, assets will be served by the esbuild dev server
+    if cfg!(debug_assertions) {
+        // multiple entrypoints are passed as a `;` separated list
+        println!(
+            "cargo::rustc-env=SPAXUM_ENTRYPOINT={}",
+            entrypoints_str.join(";")
+        );
+        // Pass the defines to the esbuild dev server, JSON encoded to allow any value
+        println!(
+            "cargo::rustc-env=SPAXUM_ESBUILD_DEFINES={}",
+            serde_json::to_string(&defines).unwrap_or_default()
+        );
+        write_asset_file(out_dir, "&[]");
+        // the esbuild dev server serves the icons and the service worker from the dist directory
+        copy_pwa_icons(&dist_dir);
+        copy_favicon(&canonical_entrypoints[0], &dist_dir);
+        bundle_service_worker_entry(&dist_dir, &defines, false);
+        log("Skipping bundling in debug mode, assets will be served by the esbuild dev server.");
+        exit(0);
+    }
+
+    // Determine the directory of the entrypoint files, and rerun the build if they change
+    let mut source_dirs: Vec<&Path> = Vec::new();
+    for entrypoint in &canonical_entrypoints {
+        let Some(source_dir) = entrypoint.parent() else {
+            error!(
+                "Unable to get parent directory of entrypoint: {}",
+                entrypoint.to_string_lossy()
+            );
+        };
+
+        if !source_dirs.contains(&source_dir) {
+            source_dirs.push(source_dir);
+        }
+    }
+
+    // Extra directories are watched and part of the cache key like the entrypoint directories
+    for extra_dir in extra_dirs {
+        if !source_dirs.contains(&extra_dir) {
+            source_dirs.push(extra_dir);
+        }
+    }
+
+    // Rerun build script if a source directory changes
+    for source_dir in &source_dirs {
+        println!("cargo::rerun-if-changed={}", source_dir.to_string_lossy());
+    }
+
+    // Rerun build script if the plugin script changes, it can be outside the source directories
+    if let Some(plugin_script) = plugin_script {
+        let Ok(plugin_script) = Path::new(plugin_script).canonicalize() else {
+            error!("{} not found!", plugin_script);
+        };
+
+        println!(
+            "cargo::rerun-if-changed={}",
+            plugin_script.to_string_lossy()
+        );
+    }
+
+    // Rerun build script if another esbuild executable is used
+    println!("cargo::rerun-if-env-changed=SPAXUM_ESBUILD_PATH");
+
+    let entrypoints_list = entrypoints_str.join(", ");
+
+    // Esbuild arguments
+    let mut args = vec!["--bundle".to_string()];
+    args.extend(entrypoints_str.iter().cloned());
+    args.extend(format.esbuild_args(&dist_dir_str));
+    args.extend([
+        format!("--metafile={manifest_file_str}"),
+        format!("--entry-names={}", entry_names.unwrap_or("[name]-[hash]")),
+        "--minify".to_string(),
+        format!("--public-path={}/", public_path()),
+    ]);
+    args.extend(ESBUILD_OPTIONS.iter().map(|option| option.to_string()));
+    args.push(format!("--color={}", COLOR.load(Ordering::Relaxed)));
+    if let Some(log_level) = *LOG_LEVEL.lock().expect("log level lock poisoned") {
+        args.push(format!("--log-level={}", log_level.as_str()));
+    }
+    args.extend(defines.iter().map(|define| format!("--define:{define}")));
+    args.extend(build_args.iter().map(|arg| arg.to_string()));
+
+    // Skip esbuild if the sources, esbuild and the arguments did not change since the last build
+    // a node_modules directory is looked up from the first entrypoint
+    let (esbuild, version) = match probe_esbuild(canonical_entrypoints[0].parent()) {
+        Ok(esbuild) => esbuild,
+        Err(e) => {
+            error!(e);
+        }
+    };
+    log(&format!("Using esbuild {version}, {esbuild}"));
+    let cache_key_file = out_dir.join(CACHE_KEY_FILE);
+    // inlining changes the output in place, so the threshold is part of the key as well
+    let mut key_args = args.clone();
+    if let Some(plugin_script) = plugin_script {
+        key_args.push(std::fs::read_to_string(plugin_script).unwrap_or_default());
+    }
+    let cache_key = cache_key(&source_dirs, &version, &key_args, inline_threshold);
+    let cached = std::fs::read_to_string(&cache_key_file).is_ok_and(|key| key == cache_key)
+        && dist_dir.exists()
+        && manifest_file.exists();
+
+    if cached {
+        log("Sources did not change, using the cached esbuild output");
+    } else {
+        match plugin_script {
+            Some(plugin_script) => {
+                bundle_node(&args, plugin_script, out_dir, &dist_dir, &entrypoints_list)
+            }
+            None => bundle_esbuild(&esbuild, &args, &dist_dir, &entrypoints_list),
+        }
+
+        if let Err(e) = std::fs::write(&cache_key_file, &cache_key) {
+            log(&format!("Unable to write the esbuild cache key: {e}"));
+        }
+    }
+
+    // Find the output files of every entrypoint in the manifest
+    let manifest = Manifest::read(&manifest_file_str);
+    // esbuild runs in the working directory of the build script
+    let working_dir = env::current_dir().unwrap_or_default();
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for entrypoint in &canonical_entrypoints {
+        let Some(entry) = manifest.entry_files(entrypoint, &working_dir) else {
+            error!(
+                "Unable to find entrypoint {} in manifest file: {}",
+                entrypoint.to_string_lossy(),
+                manifest_file_str
+            );
+        };
+
+        entries.push(entry);
+    }
+
+    if let Some(size_limit) = size_limit {
+        size_limit.check(&manifest);
+    }
+
+    if let Some(report_path) = report_path {
+        BundleAnalysis::new(&manifest).write(&manifest_file, Path::new(report_path));
+    }
+
+    // Inline small assets before the digests are computed, this changes the CSS output
+    if let Some(inline_threshold) = inline_threshold {
+        inline_small_assets(&dist_dir, inline_threshold);
+    }
+
+    // Set environment variables for the entrypoint files
+    let (js, css) = &entries[0];
+    println!("cargo::rustc-env=SPAXUM_JS_ENTRY={js}");
+    println!("cargo::rustc-env=SPAXUM_CSS_ENTRY={css}");
+
+    // Set environment variables for the subresource integrity digests of the entrypoint files
+    println!(
+        "cargo::rustc-env=SPAXUM_JS_SRI={}",
+        sri_digest(&dist_dir, js)
+    );
+    println!(
+        "cargo::rustc-env=SPAXUM_CSS_SRI={}",
+        sri_digest(&dist_dir, css)
+    );
+
+    for (index, (js, css)) in entries.iter().enumerate() {
+        println!("cargo::rustc-env=SPAXUM_JS_ENTRY_{index}={js}");
+        println!("cargo::rustc-env=SPAXUM_CSS_ENTRY_{index}={css}");
+    }
+
+    // The load! macro cannot enumerate the numbered variables, so also pass the other entries as one list
+    let additional_entries = entries[1..]
+        .iter()
+        .map(|(js, css)| format!("{js},{css}"))
+        .collect::<Vec<String>>()
+        .join(";");
+    println!("cargo::rustc-env=SPAXUM_ADDITIONAL_ENTRIES={additional_entries}");
+
+    if format == BundleFormat::Esm {
+        println!("cargo::rustc-env=SPAXUM_JS_FORMAT=esm");
+    }
+
+    if PUBLIC_PATH
+        .lock()
+        .expect("public path lock poisoned")
+        .is_some()
+    {
+        println!("cargo::rustc-env=SPAXUM_PUBLIC_PATH={}", public_path());
+    }
+
+    copy_pwa_icons(&dist_dir);
+    copy_favicon(&canonical_entrypoints[0], &dist_dir);
+    bundle_service_worker_entry(&dist_dir, &defines, true);
+
+    // Convert assets to code and write to file, the assets are compressed in parallel
+    let code = match compress::assets_to_code(&dist_dir, out_dir) {
+        Some(code) => code,
+        None => {
+            log("Unable to compress the assets in parallel, falling back to memory-serve");
+            memory_serve::assets_to_code(&dist_dir_str, &dist_dir, out_dir, true, log)
+        }
+    };
+
+    write_asset_file(out_dir, &code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn git_head_files_exist() {
+        let files = git_head_files();
+
+        assert!(files.iter().any(|file| file.ends_with("HEAD")));
+        assert!(files.iter().all(|file| file.exists()));
+    }
+
+    #[test]
+    fn entry_files_match_the_whole_path() {
+        let dir = std::env::temp_dir().join(format!("spaxum-entry-files-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("admin/src")).unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/index.ts"), "").unwrap();
+        std::fs::write(dir.join("admin/src/index.ts"), "").unwrap();
+
+        let output = |entry_point: &str| Output {
+            bytes: 0,
+            css_bundle: None,
+            entry_point: Some(entry_point.to_string()),
+            inputs: HashMap::new(),
+        };
+        let manifest = Manifest {
+            outputs: HashMap::from([
+                (
+                    "dist/index-ADMIN.js".to_string(),
+                    output("admin/src/index.ts"),
+                ),
+                ("dist/index-MAIN.js".to_string(), output("src/index.ts")),
+            ]),
+        };
+
+        let main = manifest.entry_files(&dir.join("src/index.ts").canonicalize().unwrap(), &dir);
+        let admin = manifest.entry_files(
+            &dir.join("admin/src/index.ts").canonicalize().unwrap(),
+            &dir,
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(main.unwrap().0, "index-MAIN.js");
+        assert_eq!(admin.unwrap().0, "index-ADMIN.js");
+    }
+}
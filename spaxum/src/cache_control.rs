@@ -0,0 +1,150 @@
+use axum::{
+    extract::{Request, State},
+    http::{
+        HeaderValue, StatusCode,
+        header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use memory_serve::Asset;
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+/// Cache-Control of assets with a content hash in the file name, they never change
+const HASHED_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Length of the content hash esbuild adds to the output file names
+const HASH_LENGTH: usize = 8;
+
+/// Cache-Control of the static assets served from memory, see `Spaxum::set_asset_cache_control`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AssetCacheControl {
+    pub(crate) max_age: u32,
+    pub(crate) immutable: bool,
+}
+
+impl AssetCacheControl {
+    /// Header value of the assets without a content hash
+    fn header_value(self) -> String {
+        if self.immutable {
+            format!("public, max-age={}, immutable", self.max_age)
+        } else {
+            format!("public, max-age={}", self.max_age)
+        }
+    }
+}
+
+/// Set the Cache-Control header of successful static asset responses
+/// Assets with a content hash in the file name are cached for a year, regardless of the configured max age
+pub(crate) async fn asset_cache_control_handler(
+    State(cache_control): State<AssetCacheControl>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let hashed = is_content_hashed(req.uri().path());
+    let mut response = next.run(req).await;
+
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let value = if hashed {
+        HeaderValue::from_static(HASHED_CACHE_CONTROL)
+    } else {
+        match HeaderValue::try_from(cache_control.header_value()) {
+            Ok(value) => value,
+            Err(_) => return response,
+        }
+    };
+
+    response.headers_mut().insert(CACHE_CONTROL, value);
+
+    response
+}
+
+/// ETags of the assets served from memory, by route
+pub(crate) type AssetEtags = Arc<HashMap<&'static str, HeaderValue>>;
+
+/// Collect the ETags memory serve computed for the embedded assets when bundling
+pub(crate) fn asset_etags(assets: &[Asset]) -> AssetEtags {
+    let etags = assets
+        .iter()
+        .filter(|asset| !asset.etag.is_empty())
+        .filter_map(|asset| {
+            // an entity tag is a quoted string
+            let etag = if asset.etag.starts_with('"') || asset.etag.starts_with("W/") {
+                asset.etag.to_string()
+            } else {
+                format!("\"{}\"", asset.etag)
+            };
+
+            Some((asset.route, HeaderValue::try_from(etag).ok()?))
+        })
+        .collect();
+
+    Arc::new(etags)
+}
+
+/// Set the ETag header of the static assets, and respond with `304 Not Modified` if the
+/// `If-None-Match` header of the request matches the ETag
+pub(crate) async fn asset_etag_handler(
+    State(etags): State<AssetEtags>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(etag) = etags.get(req.uri().path()).cloned() else {
+        return next.run(req).await;
+    };
+
+    let not_modified = req
+        .headers()
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| etag_matches(value, &etag));
+
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response();
+    }
+
+    let mut response = next.run(req).await;
+
+    if response.status().is_success() {
+        response.headers_mut().entry(ETAG).or_insert(etag);
+    }
+
+    response
+}
+
+/// Weak comparison of an `If-None-Match` header value with the ETag, as required for `If-None-Match`
+fn etag_matches(if_none_match: &str, etag: &HeaderValue) -> bool {
+    let opaque_tag = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque_tag(etag.to_str().unwrap_or_default());
+
+    if_none_match
+        .split(',')
+        .any(|tag| tag.trim() == "*" || opaque_tag(tag) == etag)
+}
+
+/// Check if the file name ends with a content hash, e.g. `index-5QHS2WJN.js` or `app-3f2a9c1b.css`
+/// Esbuild hashes are uppercase letters and digits, lowercase hexadecimal hashes are accepted as well
+fn is_content_hashed(path: &str) -> bool {
+    let Some(stem) = Path::new(path).file_stem().and_then(|stem| stem.to_str()) else {
+        return false;
+    };
+
+    // a source map is named after the file it maps, e.g. `index-5QHS2WJN.js.map`
+    let stem = stem.split('.').next().unwrap_or(stem);
+
+    let Some((_, hash)) = stem.rsplit_once('-') else {
+        return false;
+    };
+
+    hash.len() == HASH_LENGTH
+        && (hash
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            || hash
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)))
+}
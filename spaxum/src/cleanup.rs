@@ -0,0 +1,193 @@
+use std::sync::{Mutex, Once};
+#[cfg(unix)]
+use std::time::{Duration, Instant};
+
+/// Time esbuild gets to exit after SIGTERM, before it is killed
+#[cfg(unix)]
+const CHILD_KILL_GRACE: Duration = Duration::from_millis(500);
+
+/// Process ids of the running esbuild children
+static CHILDREN: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// Installs the exit and panic cleanup once, on the first registered child
+static INSTALL_CLEANUP: Once = Once::new();
+
+/// Register a running esbuild child, so it is stopped when the process exits abnormally
+/// `kill_on_drop` does not run on `std::process::exit` or when the runtime is not dropped
+pub(crate) fn register_child(pid: u32) {
+    INSTALL_CLEANUP.call_once(install_cleanup);
+
+    CHILDREN.lock().unwrap_or_else(|e| e.into_inner()).push(pid);
+}
+
+/// Remove an esbuild child from the registry after it exited
+pub(crate) fn unregister_child(pid: u32) {
+    CHILDREN
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|child| *child != pid);
+}
+
+/// Stop all registered esbuild children, best effort
+/// Sends SIGTERM, and SIGKILL to the children that did not exit within the grace period
+pub(crate) fn kill_children() {
+    // the lock may be poisoned when called from the panic hook
+    let pids = std::mem::take(&mut *CHILDREN.lock().unwrap_or_else(|e| e.into_inner()));
+
+    if !pids.is_empty() {
+        terminate(pids);
+    }
+}
+
+/// Stop the registered children at exit, and when the main thread panics
+fn install_cleanup() {
+    #[cfg(unix)]
+    {
+        extern "C" fn at_exit() {
+            kill_children();
+        }
+
+        // SAFETY: `at_exit` is a plain function without unwinding, called once by `exit`
+        unsafe {
+            libc::atexit(at_exit);
+        }
+    }
+
+    // panics in other threads, e.g. tokio tasks, do not end the process
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous_hook(info);
+
+        if std::thread::current().name() == Some("main") {
+            kill_children();
+        }
+    }));
+}
+
+#[cfg(unix)]
+fn terminate(mut pids: Vec<u32>) {
+    for pid in &pids {
+        // SAFETY: sending a signal has no memory safety requirements
+        unsafe {
+            libc::kill(*pid as libc::pid_t, libc::SIGTERM);
+        }
+    }
+
+    let deadline = Instant::now() + CHILD_KILL_GRACE;
+
+    while !pids.is_empty() && Instant::now() < deadline {
+        // the children may already be reaped by tokio, then waitpid fails
+        // SAFETY: waitpid is allowed a null status pointer
+        pids.retain(|pid| unsafe {
+            libc::waitpid(*pid as libc::pid_t, std::ptr::null_mut(), libc::WNOHANG) == 0
+        });
+
+        if !pids.is_empty() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    for pid in pids {
+        // SAFETY: sending a signal has no memory safety requirements
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn terminate(pids: Vec<u32>) {
+    // there is no SIGTERM, esbuild is killed right away
+    for pid in pids {
+        let _ = std::process::Command::new("taskkill")
+            .args(["/F", "/PID", &pid.to_string()])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
+    }
+}
+
+/// Guard that stops the esbuild dev server when dropped, see `Spaxum::cleanup_guard`
+#[derive(Debug)]
+pub struct CleanupGuard {
+    pub(crate) _private: (),
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        kill_children();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check if the child is in the registry, other tests register their own children concurrently
+    fn registered(pid: u32) -> bool {
+        CHILDREN
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&pid)
+    }
+
+    #[test]
+    fn unregister_removes_child() {
+        register_child(u32::MAX);
+        assert!(registered(u32::MAX));
+
+        unregister_child(u32::MAX);
+        assert!(!registered(u32::MAX));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exited_child_is_unregistered() {
+        let child = crate::dev::spawn_esbuild(std::process::Command::new("true"), &[]).unwrap();
+        let pid = child.id().unwrap();
+        let dev_state = crate::dev::DevState::default();
+        let mut shutdown = dev_state.shutdown_receiver();
+
+        crate::dev::watch_esbuild(child, &dev_state, &mut shutdown).await;
+
+        assert!(!registered(pid));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn terminate_stops_child() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+
+        terminate(vec![child.id()]);
+
+        // the child was reaped by terminate, or exited from SIGTERM
+        let exited = child.try_wait().map_or(true, |status| status.is_some());
+        assert!(exited);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stopped_child_is_unregistered() {
+        let mut command = std::process::Command::new("sleep");
+        command.arg("30");
+        let child = crate::dev::spawn_esbuild(command, &[]).unwrap();
+        let pid = child.id().unwrap();
+        let dev_state = std::sync::Arc::new(crate::dev::DevState::default());
+        let mut shutdown = dev_state.shutdown_receiver();
+
+        let watcher = tokio::spawn({
+            let dev_state = dev_state.clone();
+            async move { crate::dev::watch_esbuild(child, &dev_state, &mut shutdown).await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(registered(pid));
+
+        crate::ShutdownHandle { dev_state }.shutdown().await;
+        watcher.await.unwrap();
+        assert!(!registered(pid));
+    }
+}
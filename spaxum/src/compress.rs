@@ -0,0 +1,159 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Write as _,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::asset_manifest::content_type;
+
+/// Directory in `OUT_DIR` the compressed assets are written to
+const COMPRESSED_DIR: &str = "compressed";
+
+/// Content types worth compressing, images and fonts are compressed already
+const COMPRESS_TYPES: [&str; 6] = [
+    "text/javascript",
+    "text/css",
+    "text/html",
+    "application/json",
+    "application/wasm",
+    "image/svg+xml",
+];
+
+/// Asset of the dist directory, embedded from the original or the compressed file
+#[derive(Debug)]
+struct EmbeddedAsset {
+    route: String,
+    path: PathBuf,
+    etag: String,
+    content_type: &'static str,
+    bytes_path: PathBuf,
+    is_compressed: bool,
+}
+
+/// Compress the files of the dist directory in parallel and generate the asset code for the `load!` macro
+/// memory-serve compresses the assets one at a time and cannot be passed compressed data,
+/// so the compressed files are written to `OUT_DIR` and embedded by the generated code itself
+/// Returns `None` if an asset could not be read or written, the caller falls back to memory-serve
+pub(crate) fn assets_to_code(dist_dir: &Path, out_dir: &Path) -> Option<String> {
+    let mut files = Vec::new();
+    crate::bundle::output_files(dist_dir, &mut files);
+    files.sort();
+
+    let compressed_dir = out_dir.join(COMPRESSED_DIR);
+    // remove the compressed files of assets of a previous build
+    let _ = std::fs::remove_dir_all(&compressed_dir);
+
+    let assets = files
+        .par_iter()
+        .map(|path| embed_asset(dist_dir, &compressed_dir, path))
+        .collect::<Option<Vec<EmbeddedAsset>>>()?;
+
+    let mut code = String::from("&[\n");
+    for asset in assets {
+        let _ = writeln!(
+            code,
+            "    memory_serve::Asset {{ route: {:?}, path: {:?}, etag: {:?}, content_type: {:?}, \
+            bytes: Some(include_bytes!({:?})), is_compressed: {} }},",
+            asset.route,
+            asset.path.to_string_lossy(),
+            asset.etag,
+            asset.content_type,
+            asset.bytes_path.to_string_lossy(),
+            asset.is_compressed,
+        );
+    }
+    code.push(']');
+
+    Some(code)
+}
+
+/// Read an asset and write its compressed file, if the content type compresses and the result is smaller
+fn embed_asset(dist_dir: &Path, compressed_dir: &Path, path: &Path) -> Option<EmbeddedAsset> {
+    let relative = path.strip_prefix(dist_dir).ok()?;
+    let route = relative
+        .components()
+        .map(|component| format!("/{}", component.as_os_str().to_string_lossy()))
+        .collect::<String>();
+
+    let bytes = std::fs::read(path).ok()?;
+    let content_type = content_type(path);
+    let etag = STANDARD.encode(Sha256::digest(&bytes));
+
+    let compressed = COMPRESS_TYPES
+        .contains(&content_type)
+        .then(|| compress_brotli(&bytes))
+        .flatten()
+        .filter(|compressed| compressed.len() < bytes.len());
+
+    let (bytes_path, is_compressed) = match compressed {
+        Some(compressed) => {
+            let mut compressed_path = compressed_dir.join(relative).into_os_string();
+            compressed_path.push(".br");
+            let compressed_path = PathBuf::from(compressed_path);
+
+            std::fs::create_dir_all(compressed_path.parent()?).ok()?;
+            std::fs::write(&compressed_path, compressed).ok()?;
+
+            (compressed_path, true)
+        }
+        None => (path.to_path_buf(), false),
+    };
+
+    Some(EmbeddedAsset {
+        route,
+        path: path.to_path_buf(),
+        etag,
+        content_type,
+        bytes_path,
+        is_compressed,
+    })
+}
+
+/// Compress with brotli at the highest quality, the compression happens once at build time
+fn compress_brotli(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut writer = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+    writer.write_all(bytes).ok()?;
+
+    Some(writer.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn compresses_text_assets_only() {
+        let dir = std::env::temp_dir().join(format!("spaxum-compress-{}", std::process::id()));
+        let dist_dir = dir.join("dist");
+        std::fs::create_dir_all(dist_dir.join("chunks")).unwrap();
+
+        let js = "console.log('spaxum');\n".repeat(100);
+        std::fs::write(dist_dir.join("chunks/index.js"), &js).unwrap();
+        std::fs::write(dist_dir.join("logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+
+        let code = assets_to_code(&dist_dir, &dir).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(code.contains("route: \"/chunks/index.js\""));
+        assert!(code.contains("index.js.br\")), is_compressed: true"));
+        assert!(code.contains("route: \"/logo.png\""));
+        assert!(code.contains("logo.png\")), is_compressed: false"));
+    }
+
+    #[test]
+    fn brotli_round_trip() {
+        let input = "body { color: red; }\n".repeat(50);
+        let compressed = compress_brotli(input.as_bytes()).unwrap();
+        assert!(compressed.len() < input.len());
+
+        let mut output = String::new();
+        brotli::Decompressor::new(compressed.as_slice(), 4096)
+            .read_to_string(&mut output)
+            .unwrap();
+        assert_eq!(output, input);
+    }
+}
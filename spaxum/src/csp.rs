@@ -0,0 +1,171 @@
+/// Placeholder of the CSP nonce, see `Spaxum::with_csp_nonce`
+/// In the HTML it is replaced by the nonce, in a policy by the `'nonce-<nonce>'` source
+pub const CSP_NONCE: &str = "%CSP_NONCE%";
+
+/// Nonce of the index.html response, passed to the `Spaxum::set_process_html_per_request` hook
+/// as a request extension, e.g. for `<script nonce="...">` tags added by the hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CspNonce(pub String);
+
+/// Builder of a Content-Security-Policy header, see `Spaxum::with_csp`
+/// Directives are rendered in the order they are added, a directive added twice is replaced
+#[derive(Debug, Clone, Default)]
+pub struct CspBuilder {
+    directives: Vec<(String, Vec<String>)>,
+    report_uri: Option<String>,
+}
+
+impl CspBuilder {
+    /// Create an empty policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a directive with its sources, e.g. `directive("worker-src", &["'self'", "blob:"])`
+    pub fn directive(mut self, name: &str, sources: &[&str]) -> Self {
+        let sources = sources.iter().map(|source| source.to_string()).collect();
+
+        match self
+            .directives
+            .iter_mut()
+            .find(|(directive, _)| directive == name)
+        {
+            Some((_, existing)) => *existing = sources,
+            None => self.directives.push((name.to_string(), sources)),
+        }
+
+        self
+    }
+
+    /// Set the `default-src` directive
+    pub fn default_src(self, sources: &[&str]) -> Self {
+        self.directive("default-src", sources)
+    }
+
+    /// Set the `script-src` directive, use `CSP_NONCE` for the nonce of the index.html scripts
+    pub fn script_src(self, sources: &[&str]) -> Self {
+        self.directive("script-src", sources)
+    }
+
+    /// Set the `style-src` directive
+    pub fn style_src(self, sources: &[&str]) -> Self {
+        self.directive("style-src", sources)
+    }
+
+    /// Set the `img-src` directive
+    pub fn img_src(self, sources: &[&str]) -> Self {
+        self.directive("img-src", sources)
+    }
+
+    /// Set the `connect-src` directive
+    pub fn connect_src(self, sources: &[&str]) -> Self {
+        self.directive("connect-src", sources)
+    }
+
+    /// Set the `font-src` directive
+    pub fn font_src(self, sources: &[&str]) -> Self {
+        self.directive("font-src", sources)
+    }
+
+    /// Set the URI violations are reported to
+    pub fn report_uri(mut self, uri: &str) -> Self {
+        self.report_uri = Some(uri.to_string());
+
+        self
+    }
+
+    /// Render the policy, e.g. `script-src 'self' %CSP_NONCE%; img-src 'self' data:`
+    pub fn build(&self) -> String {
+        let mut directives = self
+            .directives
+            .iter()
+            .map(|(name, sources)| {
+                if sources.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{name} {}", sources.join(" "))
+                }
+            })
+            .collect::<Vec<String>>();
+
+        if let Some(report_uri) = &self.report_uri {
+            directives.push(format!("report-uri {report_uri}"));
+        }
+
+        directives.join("; ")
+    }
+}
+
+/// Framing policy of the responses against clickjacking, see `Spaxum::with_frame_options`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FramePolicy {
+    /// The pages may not be framed
+    Deny,
+    /// The pages may only be framed by the same origin
+    SameOrigin,
+    /// The pages may only be framed by the origin, e.g. `https://example.com`
+    AllowFrom(String),
+}
+
+impl FramePolicy {
+    /// Value of the `X-Frame-Options` header
+    /// `ALLOW-FROM` is ignored by current browsers, they use the `frame-ancestors` directive instead
+    pub(crate) fn header_value(&self) -> String {
+        match self {
+            FramePolicy::Deny => "DENY".to_string(),
+            FramePolicy::SameOrigin => "SAMEORIGIN".to_string(),
+            FramePolicy::AllowFrom(origin) => format!("ALLOW-FROM {origin}"),
+        }
+    }
+
+    /// Sources of the equivalent `frame-ancestors` directive
+    fn frame_ancestors(&self) -> &str {
+        match self {
+            FramePolicy::Deny => "'none'",
+            FramePolicy::SameOrigin => "'self'",
+            FramePolicy::AllowFrom(origin) => origin,
+        }
+    }
+
+    /// Append the `frame-ancestors` directive to a built policy, unless the policy has one
+    pub(crate) fn append_to_policy(&self, policy: &str) -> String {
+        let has_frame_ancestors = policy
+            .split(';')
+            .any(|directive| directive.split_whitespace().next() == Some("frame-ancestors"));
+
+        if has_frame_ancestors {
+            policy.to_string()
+        } else if policy.trim().is_empty() {
+            format!("frame-ancestors {}", self.frame_ancestors())
+        } else {
+            format!("{policy}; frame-ancestors {}", self.frame_ancestors())
+        }
+    }
+}
+
+/// Replace the nonce sentinel of a built policy by the nonce source, or remove it without a nonce
+pub(crate) fn render_policy(policy: &str, nonce: Option<&str>) -> String {
+    match nonce {
+        Some(nonce) => policy.replace(CSP_NONCE, &format!("'nonce-{nonce}'")),
+        None => policy
+            .replace(&format!(" {CSP_NONCE}"), "")
+            .replace(CSP_NONCE, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_policy_with_html_placeholder() {
+        let policy = CspBuilder::new().script_src(&["'self'", CSP_NONCE]).build();
+
+        assert_eq!(policy, "script-src 'self' %CSP_NONCE%");
+        assert_eq!(
+            render_policy(&policy, Some("abc")),
+            "script-src 'self' 'nonce-abc'"
+        );
+        assert_eq!(render_policy(&policy, None), "script-src 'self'");
+    }
+}
@@ -0,0 +1,826 @@
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use serde::Serialize;
+use std::{
+    convert::Infallible,
+    process::{ExitStatus, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::AsyncBufReadExt,
+    process::{Child, Command},
+    runtime::RuntimeFlavor,
+    sync::{broadcast, watch},
+    task::JoinHandle,
+};
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+
+use crate::cleanup::{register_child, unregister_child};
+
+/// Default maximum number of esbuild restarts
+pub(crate) const ESBUILD_MAX_RESTARTS: u32 = 5;
+
+/// Delay before restarting esbuild after it exited
+pub(crate) const ESBUILD_RESTART_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Number of esbuild exits within the crash window after which esbuild is not restarted anymore
+pub(crate) const ESBUILD_CRASH_LIMIT: usize = 3;
+
+/// Time window in which consecutive esbuild exits count as crashes
+pub(crate) const ESBUILD_CRASH_WINDOW: Duration = Duration::from_secs(5);
+
+/// Maximum time a proxied request waits for the esbuild dev server to start listening
+pub(crate) const ESBUILD_READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Window in which identical consecutive esbuild output lines are collapsed into one
+const LOG_DEDUPE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Shared state of the esbuild dev server, updated from the esbuild output
+#[derive(Debug)]
+pub(crate) struct DevState {
+    build_error: Mutex<Option<String>>,
+    process: Mutex<ProcessStatus>,
+    rebuilds: AtomicU64,
+    restarts: AtomicU32,
+    ready: watch::Sender<bool>,
+    events: broadcast::Sender<BuildEvent>,
+    shutdown: watch::Sender<bool>,
+    started: AtomicBool,
+    running: watch::Sender<bool>,
+    track_output: AtomicBool,
+    serving: AtomicBool,
+    log_quiet: AtomicBool,
+    on_rebuild: Mutex<Option<OnRebuild>>,
+}
+
+impl Default for DevState {
+    fn default() -> Self {
+        Self {
+            build_error: Mutex::new(None),
+            process: Mutex::new(ProcessStatus::default()),
+            rebuilds: AtomicU64::new(0),
+            restarts: AtomicU32::new(0),
+            ready: watch::Sender::new(false),
+            events: broadcast::channel(16).0,
+            shutdown: watch::Sender::new(false),
+            started: AtomicBool::new(false),
+            running: watch::Sender::new(false),
+            track_output: AtomicBool::new(true),
+            serving: AtomicBool::new(true),
+            log_quiet: AtomicBool::new(false),
+            on_rebuild: Mutex::new(None),
+        }
+    }
+}
+
+impl DevState {
+    /// Tell the connected browsers about a finished build
+    fn send_event(&self, event: BuildEvent) {
+        // sending only fails when no browser is connected
+        let _ = self.events.send(event);
+    }
+
+    /// Wait until the esbuild dev server is listening, returns false if it is not ready in time
+    pub(crate) async fn wait_ready(&self, timeout: Duration) -> bool {
+        let mut ready = self.ready.subscribe();
+
+        tokio::time::timeout(timeout, async {
+            // the borrowed value is dropped here, it must not be held across an await
+            let _ = ready.wait_for(|ready| *ready).await;
+        })
+        .await
+        .is_ok()
+    }
+
+    /// Mark the dev server as listening, or not
+    pub(crate) fn set_ready(&self, ready: bool) {
+        self.ready.send_replace(ready);
+    }
+
+    /// Receiver that is notified when esbuild should shut down
+    pub(crate) fn shutdown_receiver(&self) -> watch::Receiver<bool> {
+        self.shutdown.subscribe()
+    }
+
+    /// Mark esbuild as started, returns false if it was already started before
+    pub(crate) fn start(&self) -> bool {
+        !self.started.swap(true, Ordering::SeqCst)
+    }
+
+    /// Mark the task that supervises the esbuild process as running, or finished
+    pub(crate) fn set_running(&self, running: bool) {
+        self.running.send_replace(running);
+    }
+
+    /// Count a restart of esbuild after it exited
+    pub(crate) fn restarted(&self) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get the number of times esbuild was restarted, esbuild is degraded if it had to be restarted
+    pub(crate) fn restarts(&self) -> u32 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    /// Count a finished build that was not tracked from the esbuild watcher output, e.g. bundling once
+    pub(crate) fn build_finished(&self) {
+        self.rebuilds.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Reload the browsers after the dist directory changed, when spaxum serves it from disk
+    /// Without the esbuild output the change is counted as a finished build
+    pub(crate) fn dist_changed(&self) {
+        if !self.track_output.load(Ordering::Relaxed) {
+            self.rebuilds.fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.send_event(BuildEvent::Reload);
+    }
+
+    /// Check if the first build finished, without the esbuild output it is assumed to be finished
+    fn first_build_finished(&self) -> bool {
+        self.rebuilds.load(Ordering::Relaxed) > 0 || !self.track_output.load(Ordering::Relaxed)
+    }
+
+    /// Get the errors of the last build, if it failed
+    pub(crate) fn build_error(&self) -> Option<String> {
+        self.build_error
+            .lock()
+            .expect("dev state lock poisoned")
+            .clone()
+    }
+
+    fn set_build_error(&self, build_error: Option<String>) {
+        *self.build_error.lock().expect("dev state lock poisoned") = build_error;
+    }
+
+    /// Report that esbuild could not be started or failed to bundle, shown by the ready route and the error overlay
+    pub(crate) fn start_failed(&self, error: String) {
+        self.set_build_error(Some(error));
+    }
+
+    /// Set whether the esbuild output shows when esbuild is listening and when builds finish,
+    /// this depends on the esbuild log level
+    pub(crate) fn set_track_output(&self, track_output: bool) {
+        self.track_output.store(track_output, Ordering::Relaxed);
+    }
+
+    /// Set whether esbuild serves the files itself, or only watches the sources and spaxum serves the dist directory
+    pub(crate) fn set_serving(&self, serving: bool) {
+        self.serving.store(serving, Ordering::Relaxed);
+    }
+
+    /// Only forward the errors and warnings of the esbuild output, or all output
+    pub(crate) fn set_log_quiet(&self, log_quiet: bool) {
+        self.log_quiet.store(log_quiet, Ordering::Relaxed);
+    }
+
+    /// Set the callback that is invoked after every build
+    pub(crate) fn set_on_rebuild(&self, on_rebuild: Arc<RebuildCallback>) {
+        *self.on_rebuild.lock().expect("dev state lock poisoned") = Some(OnRebuild(on_rebuild));
+    }
+
+    /// Invoke the rebuild callback on a blocking task, so a slow callback does not hold up the esbuild output
+    fn rebuilt(&self, event: RebuildEvent) {
+        let on_rebuild = self
+            .on_rebuild
+            .lock()
+            .expect("dev state lock poisoned")
+            .clone();
+
+        if let Some(OnRebuild(on_rebuild)) = on_rebuild {
+            tokio::task::spawn_blocking(move || on_rebuild(event));
+        }
+    }
+
+    /// Record the esbuild process that was spawned, or the exit status when it exited
+    fn set_process(&self, process: ProcessStatus) {
+        *self.process.lock().expect("dev state lock poisoned") = process;
+    }
+
+    /// Get the status of the esbuild process
+    pub(crate) fn status(&self) -> DevStatus {
+        let process = *self.process.lock().expect("dev state lock poisoned");
+
+        DevStatus {
+            running: process.pid.is_some(),
+            pid: process.pid,
+            last_exit_status: process.exit_status,
+            rebuilds: self.rebuilds.load(Ordering::Relaxed),
+            restarts: self.restarts(),
+            last_error: self.build_error(),
+        }
+    }
+}
+
+/// Process id of the running esbuild process, or the exit status of the last one
+#[derive(Debug, Default, Clone, Copy)]
+struct ProcessStatus {
+    pid: Option<u32>,
+    exit_status: Option<ExitStatus>,
+}
+
+/// Status of the esbuild process, see `Spaxum::dev_status`
+#[derive(Debug, Clone, Default)]
+pub struct DevStatus {
+    /// Whether the esbuild process is running
+    pub running: bool,
+    /// Process id of the running esbuild process
+    pub pid: Option<u32>,
+    /// Exit status of the last esbuild process that exited, e.g. before a restart
+    pub last_exit_status: Option<ExitStatus>,
+    /// Number of builds that esbuild completed, successful or not
+    pub rebuilds: u64,
+    /// Number of times esbuild was restarted after it exited
+    pub restarts: u32,
+    /// Errors of the last build, if it failed
+    pub last_error: Option<String>,
+}
+
+/// Callback invoked after every esbuild build, see `Spaxum::on_rebuild`
+pub(crate) type RebuildCallback = dyn Fn(RebuildEvent) + Send + Sync;
+
+/// Rebuild callback of the dev state
+#[derive(Clone)]
+struct OnRebuild(Arc<RebuildCallback>);
+
+impl std::fmt::Debug for OnRebuild {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("OnRebuild")
+    }
+}
+
+/// Finished esbuild build in development, passed to the `Spaxum::on_rebuild` callback
+#[derive(Debug, Clone)]
+pub struct RebuildEvent {
+    /// Whether the build succeeded
+    pub success: bool,
+    /// Time between the start and the end of the build, unknown for the first build
+    pub duration: Option<Duration>,
+    /// The esbuild output lines of the build
+    pub output: Vec<String>,
+}
+
+/// Result of a finished build, sent to the browsers
+#[derive(Debug, Clone)]
+enum BuildEvent {
+    /// The build succeeded, the page should reload
+    Reload,
+    /// The build failed with the given errors
+    Error(String),
+}
+
+/// Parses the output of the esbuild watcher to track the result of each build
+#[derive(Debug, Default)]
+pub(crate) struct BuildOutput {
+    errors: Vec<String>,
+    in_error: bool,
+    lines: Vec<String>,
+    started: Option<Instant>,
+}
+
+impl BuildOutput {
+    /// Process a line of esbuild output, the dev state is updated when a build finishes
+    pub(crate) fn line(&mut self, line: &str, state: &DevState) {
+        // esbuild logs the address it serves on once it is listening, e.g. " > Local:   http://127.0.0.1:8888/"
+        if line.contains("Local:") {
+            state.set_ready(true);
+
+            return;
+        }
+
+        if line.contains("[watch] build started") {
+            self.errors.clear();
+            self.in_error = false;
+            self.lines.clear();
+            self.started = Some(Instant::now());
+
+            return;
+        }
+
+        if line.contains("[watch] build finished") {
+            state.rebuilds.fetch_add(1, Ordering::Relaxed);
+            let build_error = self.errors.join("\n").trim().to_string();
+
+            state.rebuilt(RebuildEvent {
+                success: build_error.is_empty(),
+                duration: self.started.take().map(|started| started.elapsed()),
+                output: std::mem::take(&mut self.lines),
+            });
+
+            if build_error.is_empty() {
+                // serving from disk the reload follows the dist directory, see `watch_dist_dir`,
+                // a build that fixes an error can write the same files as before, so it reloads here
+                let had_error = state.build_error().is_some();
+                state.set_build_error(None);
+
+                if state.serving.load(Ordering::Relaxed) || had_error {
+                    state.send_event(BuildEvent::Reload);
+                }
+            } else {
+                state.set_build_error(Some(build_error.clone()));
+                state.send_event(BuildEvent::Error(build_error));
+            }
+
+            self.errors.clear();
+            self.in_error = false;
+
+            return;
+        }
+
+        self.lines.push(line.to_string());
+
+        // an error block starts with "✘ [ERROR]" and ends at a warning or the "1 error" summary
+        if line.contains("[ERROR]") {
+            self.in_error = true;
+        } else if line.contains("[WARNING]") || is_summary(line) {
+            self.in_error = false;
+        }
+
+        if self.in_error {
+            self.errors.push(line.to_string());
+        }
+    }
+}
+
+/// Forwards the esbuild output, collapsing bursts of identical lines, e.g. when an editor saves a file
+/// several times, and hiding everything but errors and warnings in quiet mode
+/// Error and warning blocks are always forwarded as is
+#[derive(Debug, Default)]
+struct LogForwarder {
+    quiet: bool,
+    in_diagnostic: bool,
+    last: Option<(String, Instant)>,
+    repeats: usize,
+}
+
+impl LogForwarder {
+    /// Forward a line of esbuild output, `stripped` is the line without ANSI colors
+    fn line(&mut self, line: &str, stripped: &str, stderr: bool) {
+        if !self.forwards(stripped) {
+            return;
+        }
+
+        if stderr {
+            eprintln!("esbuild: {line}");
+        } else {
+            println!("esbuild: {line}");
+        }
+    }
+
+    /// Check if a line is forwarded, the repeats of the previous line are reported before it is
+    fn forwards(&mut self, stripped: &str) -> bool {
+        // a diagnostic block starts with "✘ [ERROR]" or "▲ [WARNING]" and ends at the "1 error" summary
+        if stripped.contains("[ERROR]") || stripped.contains("[WARNING]") {
+            self.in_diagnostic = true;
+        } else if stripped.contains("[watch]") {
+            self.in_diagnostic = false;
+        }
+
+        let summary = is_summary(stripped);
+        let diagnostic = self.in_diagnostic || summary;
+
+        if summary {
+            self.in_diagnostic = false;
+        }
+
+        if !diagnostic {
+            if self.quiet {
+                return false;
+            }
+
+            let now = Instant::now();
+            if let Some((last, at)) = &mut self.last
+                && last == stripped
+                && now.duration_since(*at) < LOG_DEDUPE_WINDOW
+            {
+                self.repeats += 1;
+                *at = now;
+
+                return false;
+            }
+        }
+
+        self.flush();
+        self.last = (!diagnostic).then(|| (stripped.to_string(), Instant::now()));
+
+        true
+    }
+
+    /// Report the lines that were collapsed into the last forwarded line
+    fn flush(&mut self) {
+        if self.repeats > 0 {
+            eprintln!("esbuild: last line repeated {} more time(s)", self.repeats);
+            self.repeats = 0;
+        }
+    }
+}
+
+/// Remove the ANSI escape sequences of colored esbuild output, so the output can be parsed
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // a control sequence ends with a letter, e.g. "\x1b[31m"
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+
+    stripped
+}
+
+/// Check if the line is the esbuild summary, e.g. "1 warning and 2 errors"
+fn is_summary(line: &str) -> bool {
+    line.starts_with(|c: char| c.is_ascii_digit())
+        && (line.contains("error") || line.contains("warning"))
+}
+
+/// Readiness of the esbuild dev server
+#[derive(Serialize)]
+pub(crate) struct ReadyResponse {
+    ready: bool,
+    listening: bool,
+    first_build_finished: bool,
+    error: Option<String>,
+}
+
+/// Report whether the esbuild dev server is listening and finished its first build,
+/// `503 Service Unavailable` until it is, so tests can wait for the assets
+pub(crate) async fn ready_handler(State(state): State<Arc<DevState>>) -> Response {
+    let listening = *state.ready.borrow();
+    let first_build_finished = state.first_build_finished();
+    let ready = listening && first_build_finished;
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let response = ReadyResponse {
+        ready,
+        listening,
+        first_build_finished,
+        error: state.build_error(),
+    };
+
+    (status, Json(response)).into_response()
+}
+
+/// Build error response, `null` if the last build succeeded
+#[derive(Serialize)]
+pub(crate) struct BuildErrorResponse {
+    error: Option<String>,
+}
+
+/// Report the errors of the last esbuild build, used by the error overlay
+pub(crate) async fn build_error_handler(
+    State(state): State<Arc<DevState>>,
+) -> Json<BuildErrorResponse> {
+    Json(BuildErrorResponse {
+        error: state.build_error(),
+    })
+}
+
+/// Server-sent events stream that emits a "reload" event after every successful build,
+/// and a "build-error" event with the errors after every failed build
+pub(crate) async fn reload_handler(
+    State(state): State<Arc<DevState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // a lagging receiver missed builds, the page is reloaded to show the latest state
+    let stream = BroadcastStream::new(state.events.subscribe()).map(|event| {
+        Ok(match event {
+            Ok(BuildEvent::Error(error)) => Event::default().event("build-error").data(error),
+            _ => Event::default().event("reload").data("reload"),
+        })
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Spawn the esbuild dev server with the given arguments
+/// The command runs the resolved esbuild executable, the arguments are added to it
+pub(crate) fn spawn_esbuild(
+    esbuild: std::process::Command,
+    args: &[String],
+) -> std::io::Result<Child> {
+    Command::from(esbuild)
+        .args(args)
+        .kill_on_drop(true)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+/// Forward the esbuild output and track the build results
+/// Returns when the esbuild process exits, or after killing it when a shutdown is requested
+pub(crate) async fn watch_esbuild(
+    mut child: Child,
+    dev_state: &DevState,
+    shutdown: &mut watch::Receiver<bool>,
+) {
+    let mut build_output = BuildOutput::default();
+    let mut log = LogForwarder {
+        quiet: dev_state.log_quiet.load(Ordering::Relaxed),
+        ..Default::default()
+    };
+    let pid = child.id();
+    dev_state.set_process(ProcessStatus {
+        pid,
+        exit_status: None,
+    });
+
+    if let Some(pid) = pid {
+        register_child(pid);
+    }
+
+    // without the esbuild output the dev server is assumed to be listening right away,
+    // without the esbuild dev server spaxum serves the dist directory and esbuild never logs it is listening
+    if !dev_state.track_output.load(Ordering::Relaxed) || !dev_state.serving.load(Ordering::Relaxed)
+    {
+        dev_state.set_ready(true);
+    }
+
+    let stdout = child
+        .stdout
+        .take()
+        .expect("esbuild did not have a handle to stdout");
+    let mut stdout_reader = tokio::io::BufReader::new(stdout).lines();
+    let mut stdout_open = true;
+
+    let stderr = child
+        .stderr
+        .take()
+        .expect("esbuild did not have a handle to stderr");
+    let mut stderr_reader = tokio::io::BufReader::new(stderr).lines();
+    let mut stderr_open = true;
+
+    loop {
+        tokio::select! {
+            next_line = stdout_reader.next_line(), if stdout_open => {
+                if let Ok(Some(line)) = next_line {
+                    let stripped = strip_ansi(&line);
+                    build_output.line(&stripped, dev_state);
+                    log.line(&line, &stripped, false);
+                } else {
+                    eprintln!("esbuild: stdout closed");
+                    stdout_open = false;
+                }
+            },
+            next_error_line = stderr_reader.next_line(), if stderr_open => {
+                if let Ok(Some(line)) = next_error_line {
+                    let stripped = strip_ansi(&line);
+                    build_output.line(&stripped, dev_state);
+                    log.line(&line, &stripped, true);
+                } else {
+                    eprintln!("esbuild: stderr closed");
+                    stderr_open = false;
+                }
+            },
+            process_result = child.wait() => {
+                dev_state.set_ready(false);
+                log.flush();
+
+                if let Some(pid) = pid {
+                    unregister_child(pid);
+                }
+
+                match process_result {
+                    Ok(exit_status) => {
+                        dev_state.set_process(ProcessStatus {
+                            pid: None,
+                            exit_status: Some(exit_status),
+                        });
+
+                        if exit_status.success() {
+                            println!("esbuild process exited successfully");
+                        } else {
+                            eprintln!("esbuild process exited with error: {exit_status}");
+                        }
+                    }
+                    Err(e) => {
+                        dev_state.set_process(ProcessStatus::default());
+                        eprintln!("esbuild process failed to exit: {e}");
+                    }
+                }
+
+                return;
+            }
+            _ = shutdown_requested(shutdown) => {
+                dev_state.set_ready(false);
+                log.flush();
+
+                if let Err(e) = child.kill().await {
+                    eprintln!("esbuild process failed to stop: {e}");
+                }
+
+                if let Some(pid) = pid {
+                    unregister_child(pid);
+                }
+
+                dev_state.set_process(ProcessStatus {
+                    pid: None,
+                    exit_status: child.try_wait().ok().flatten(),
+                });
+
+                return;
+            }
+        }
+    }
+}
+
+/// Run blocking work, like an esbuild build, from a synchronous method that may be called on the runtime
+/// On a multi-threaded runtime the worker thread is handed off, so the other tasks keep running,
+/// a current thread runtime cannot hand off its only thread
+pub(crate) fn block_in_place<T>(f: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+/// Wait until a shutdown of esbuild is requested
+pub(crate) async fn shutdown_requested(shutdown: &mut watch::Receiver<bool>) {
+    // the borrowed value is dropped here, it must not be held across an await
+    let _ = shutdown.wait_for(|shutdown| *shutdown).await;
+}
+
+/// Handle to stop the esbuild dev server, see `Spaxum::shutdown_handle`
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    pub(crate) dev_state: Arc<DevState>,
+}
+
+impl ShutdownHandle {
+    /// Kill the esbuild dev server and wait for it to exit
+    /// Does nothing if esbuild is not running, e.g. when assets are served from memory
+    pub async fn shutdown(&self) {
+        self.dev_state.shutdown.send_replace(true);
+
+        let mut running = self.dev_state.running.subscribe();
+        // the borrowed value is dropped here, it must not be held across an await
+        let _ = running.wait_for(|running| !*running).await;
+    }
+}
+
+/// Handle to the esbuild dev server started by `Spaxum::start_proxy`
+#[derive(Debug)]
+pub struct SpaxumDevHandle {
+    pub(crate) task: JoinHandle<()>,
+    pub(crate) dev_state: Arc<DevState>,
+}
+
+impl SpaxumDevHandle {
+    /// Get a handle to stop the esbuild dev server
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            dev_state: self.dev_state.clone(),
+        }
+    }
+
+    /// Get the status of the esbuild process
+    pub fn status(&self) -> DevStatus {
+        self.dev_state.status()
+    }
+
+    /// Get the task that supervises esbuild, it finishes when esbuild is stopped or gave up restarting
+    pub fn into_join_handle(self) -> JoinHandle<()> {
+        self.task
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed the lines to the build output, like esbuild writes them
+    fn build(output: &mut BuildOutput, state: &DevState, lines: &[&str]) {
+        for line in lines {
+            output.line(&strip_ansi(line), state);
+        }
+    }
+
+    #[test]
+    fn strip_ansi_colors() {
+        assert_eq!(
+            strip_ansi(
+                "\x1b[31m✘ \x1b[41;31m[\x1b[41;97mERROR\x1b[41;31m]\x1b[0m \x1b[1mExpected \";\"\x1b[0m"
+            ),
+            "✘ [ERROR] Expected \";\""
+        );
+        assert_eq!(
+            strip_ansi("[watch] build finished"),
+            "[watch] build finished"
+        );
+    }
+
+    #[test]
+    fn build_output_tracks_errors() {
+        let state = DevState::default();
+        let mut output = BuildOutput::default();
+
+        build(
+            &mut output,
+            &state,
+            &[
+                "[watch] build started (change: \"index.js\")",
+                "\x1b[31m✘ \x1b[41;31m[\x1b[41;97mERROR\x1b[41;31m]\x1b[0m \x1b[1mExpected \";\" but found \"}\"\x1b[0m",
+                "    index.js:1:12:",
+                "▲ [WARNING] Unused variable",
+                "1 warning and 1 error",
+                "[watch] build finished",
+            ],
+        );
+
+        let error = state.build_error().expect("the build failed");
+        assert!(error.starts_with("✘ [ERROR] Expected"));
+        assert!(error.contains("index.js:1:12:"));
+        assert!(!error.contains("[WARNING]"));
+        assert_eq!(state.status().rebuilds, 1);
+
+        build(
+            &mut output,
+            &state,
+            &["[watch] build started", "[watch] build finished"],
+        );
+
+        assert_eq!(state.build_error(), None);
+        assert_eq!(state.status().rebuilds, 2);
+    }
+
+    #[test]
+    fn build_output_tracks_listening() {
+        let state = DevState::default();
+        let mut output = BuildOutput::default();
+
+        build(
+            &mut output,
+            &state,
+            &["", " > Local:   \x1b[4mhttp://127.0.0.1:8888/\x1b[0m"],
+        );
+
+        assert!(*state.ready.borrow());
+        assert!(!state.first_build_finished());
+    }
+
+    #[test]
+    fn log_forwarder_collapses_repeats() {
+        let mut log = LogForwarder::default();
+
+        assert!(log.forwards("[watch] build started (change: \"index.js\")"));
+        assert!(!log.forwards("[watch] build started (change: \"index.js\")"));
+        assert!(!log.forwards("[watch] build started (change: \"index.js\")"));
+        assert_eq!(log.repeats, 2);
+
+        // the next line reports the repeats first
+        assert!(log.forwards("[watch] build finished"));
+        assert_eq!(log.repeats, 0);
+    }
+
+    #[test]
+    fn log_forwarder_keeps_diagnostics() {
+        let mut log = LogForwarder::default();
+
+        // identical lines of an error block are not collapsed
+        assert!(log.forwards("✘ [ERROR] Could not resolve \"react\""));
+        assert!(log.forwards(""));
+        assert!(log.forwards(""));
+        assert!(log.forwards("1 error"));
+        assert_eq!(log.repeats, 0);
+    }
+
+    #[test]
+    fn log_forwarder_quiet() {
+        let mut log = LogForwarder {
+            quiet: true,
+            ..Default::default()
+        };
+
+        assert!(!log.forwards("[watch] build started"));
+        assert!(log.forwards(&strip_ansi("\x1b[33m▲ [WARNING]\x1b[0m Unused variable")));
+        assert!(log.forwards("    index.js:3:6:"));
+        assert!(log.forwards("1 warning"));
+        assert!(!log.forwards("[watch] build finished"));
+    }
+}
@@ -0,0 +1,133 @@
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    extract::Request,
+    http::header::CONTENT_ENCODING,
+};
+use tower_service::Service;
+
+/// Inline the CSS entry in the index.html if it is small enough, see `Spaxum::set_inline_css`
+pub(crate) struct InlineCss {
+    /// Router of the assets served from memory
+    pub(crate) router: Router,
+    /// Route of the CSS entry in the asset router
+    pub(crate) route: String,
+    /// URL of the CSS entry in the index.html, including the static prefix
+    pub(crate) href: String,
+    pub(crate) threshold_bytes: usize,
+    pub(crate) nonce: bool,
+}
+
+impl InlineCss {
+    /// Replace the stylesheet link of the CSS entry by a style element with its contents
+    /// The HTML is returned as is if the CSS is too large or the link is not found
+    pub(crate) async fn apply(&self, html: String) -> String {
+        let Some((start, end)) = stylesheet_link(&html, &self.href) else {
+            return html;
+        };
+
+        let Some(css) = self.fetch().await else {
+            return html;
+        };
+
+        // relative URLs in the stylesheet are relative to the stylesheet, not the page
+        let base = match self.href.rsplit_once('/') {
+            Some((base, _)) => base,
+            None => "",
+        };
+        let css = rebase_urls(&css, base);
+
+        let nonce = if self.nonce {
+            " nonce=\"%CSP_NONCE%\""
+        } else {
+            ""
+        };
+
+        format!(
+            "{}<style{nonce}>{css}</style>{}",
+            &html[..start],
+            &html[end..]
+        )
+    }
+
+    /// Request the uncompressed CSS entry from the asset router, `None` above the threshold
+    async fn fetch(&self) -> Option<String> {
+        let request = Request::get(&self.route).body(Body::empty()).ok()?;
+
+        let mut router = self.router.clone();
+        let response = match router.call(request).await {
+            Ok(response) => response,
+            Err(e) => match e {},
+        };
+
+        if !response.status().is_success() || response.headers().contains_key(CONTENT_ENCODING) {
+            return None;
+        }
+
+        // reading fails if the body is larger than the limit
+        let bytes = to_bytes(response.into_body(), self.threshold_bytes)
+            .await
+            .ok()?;
+
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+/// Byte range of the `<link rel="stylesheet">` tag that refers to the URL
+fn stylesheet_link(html: &str, href: &str) -> Option<(usize, usize)> {
+    let href = format!("href=\"{href}\"");
+    let mut offset = 0;
+
+    while let Some(start) = html[offset..].find("<link").map(|start| offset + start) {
+        let end = start + html[start..].find('>')? + 1;
+        let tag = &html[start..end];
+
+        if tag.contains("stylesheet") && tag.contains(&href) {
+            return Some((start, end));
+        }
+
+        offset = end;
+    }
+
+    None
+}
+
+/// Resolve the relative `url(...)` references of a stylesheet against the base path
+fn rebase_urls(css: &str, base: &str) -> String {
+    let mut rebased = String::with_capacity(css.len());
+    let mut rest = css;
+
+    while let Some(start) = rest.find("url(") {
+        let (before, after) = rest.split_at(start + "url(".len());
+        rebased.push_str(before);
+
+        let Some(end) = after.find(')') else {
+            rest = after;
+            break;
+        };
+
+        let url = after[..end].trim();
+        let (quote, unquoted) = match url.chars().next() {
+            Some(quote @ ('"' | '\'')) => (quote.to_string(), url.trim_matches(quote)),
+            _ => (String::new(), url),
+        };
+
+        let absolute = unquoted.starts_with('/')
+            || unquoted.starts_with('#')
+            || unquoted.starts_with("data:")
+            || unquoted.contains("://");
+
+        if absolute || unquoted.is_empty() {
+            rebased.push_str(&after[..end]);
+        } else {
+            let relative = unquoted.trim_start_matches("./");
+            rebased.push_str(&format!("{quote}{base}/{relative}{quote}"));
+        }
+
+        rest = &after[end..];
+    }
+
+    rebased.push_str(rest);
+
+    rebased
+}
@@ -1,63 +1,533 @@
 use axum::{
-    Router,
+    Json, Router,
     extract::{Request, State},
-    http::{HeaderValue, header::HOST},
-    response::{Html, Response},
-    routing::get,
+    handler::Handler,
+    http::{
+        HeaderMap, HeaderName, HeaderValue, StatusCode, Uri,
+        header::{
+            ACCEPT_LANGUAGE, CACHE_CONTROL, CONTENT_SECURITY_POLICY, CONTENT_TYPE, LINK,
+            STRICT_TRANSPORT_SECURITY, VARY, X_FRAME_OPTIONS,
+        },
+        request::Parts,
+    },
+    middleware::{from_fn, from_fn_with_state, map_response_with_state},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{MethodRouter, get},
 };
-use hyper::{StatusCode, Uri};
-use hyper_util::{client::legacy::connect::HttpConnector, rt::TokioExecutor};
+use base64::{Engine, engine::general_purpose::STANDARD};
 use memory_serve::{Asset, MemoryServe};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    env,
-    io::BufRead,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     path::{Path, PathBuf},
-    process::{Stdio, exit},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
 };
-use tokio::{io::AsyncBufReadExt, process::Command};
+use tokio::sync::OnceCell;
+use tower_service::Service;
 
 pub use memory_serve;
 
-/// HTTP client to proxy request in development
-type Client = hyper_util::client::legacy::Client<
-    hyper_util::client::legacy::connect::HttpConnector,
-    axum::body::Body,
->;
+mod asset_manifest;
+mod bundle;
+mod cache_control;
+mod cleanup;
+mod compress;
+mod csp;
+mod dev;
+mod inline_css;
+mod locale;
+mod minify;
+mod multi_page;
+mod open_graph;
+mod proxy;
+mod pwa;
+mod range;
+#[cfg(feature = "tls")]
+mod tls;
+mod watch_dir;
+
+pub use bundle::{
+    BundleFormat, LogLevel, bundle, bundle_error_on_size_limit, bundle_multiple,
+    bundle_service_worker, bundle_watch_dirs, bundle_with_analysis, bundle_with_args,
+    bundle_with_entry_names, bundle_with_externals, bundle_with_format, bundle_with_git_hash,
+    bundle_with_inline_threshold, bundle_with_plugin_script, bundle_with_size_limit,
+    bundle_with_source_maps, bundle_with_targets, check_types, define_from_cargo_feature,
+    include_favicon, include_pwa_icons, inject_env_var, inject_pkg_version, set_esbuild_color,
+    set_esbuild_log_level, set_public_path, set_static_path,
+};
+use cache_control::{
+    AssetCacheControl, asset_cache_control_handler, asset_etag_handler, asset_etags,
+};
+pub use cleanup::CleanupGuard;
+use csp::render_policy;
+pub use csp::{CSP_NONCE, CspBuilder, CspNonce, FramePolicy};
+use dev::{
+    DevState, ESBUILD_CRASH_LIMIT, ESBUILD_CRASH_WINDOW, ESBUILD_MAX_RESTARTS,
+    ESBUILD_RESTART_BACKOFF, block_in_place, build_error_handler, ready_handler, reload_handler,
+    shutdown_requested, spawn_esbuild, watch_esbuild,
+};
+pub use dev::{DevStatus, RebuildEvent, ShutdownHandle, SpaxumDevHandle};
+use inline_css::InlineCss;
+use locale::best_locale;
+use minify::HtmlMinification;
+pub use multi_page::SpaxumMultiPage;
+pub use open_graph::OpenGraph;
+use proxy::{
+    ESBUILD_DEV_HOST, ProxyState, ProxyTimeouts, dev_health_handler, dev_port_from_env,
+    parse_dev_server_url, proxy_handler,
+};
+use pwa::PWA_MANIFEST_ROUTE;
+pub use pwa::{PwaDisplay, PwaIcon, PwaManifest};
+use range::range_handler;
+use watch_dir::{dist_file_handler, watch_dist_dir};
 
 /// File names for the entrypoint files (js, css)
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct EntryFiles {
     pub js: String,
     pub css: String,
+    /// Base64 encoded SHA-384 digest of the js entry, for subresource integrity
+    pub js_sri: String,
+    /// Base64 encoded SHA-384 digest of the css entry, for subresource integrity
+    pub css_sri: String,
+    /// File names (js, css) of additional entrypoints, see `bundle_multiple`
+    pub additional: Vec<(String, String)>,
+    /// Whether the scripts are ES modules
+    pub module: bool,
+}
+
+impl EntryFiles {
+    /// Parse a list of additional entries in the form `js,css;js,css`
+    pub fn parse_entries(entries: &str) -> Vec<(String, String)> {
+        entries
+            .split(';')
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (js, css) = entry.split_once(',').unwrap_or((entry, ""));
+
+                (js.to_string(), css.to_string())
+            })
+            .collect()
+    }
+
+    /// Hash sources of the entry files for a Content-Security-Policy, e.g. `'sha384-<digest>'`
+    /// Empty in development, the digests are only computed for release builds
+    pub fn integrity_sources(&self) -> Vec<String> {
+        [&self.js_sri, &self.css_sri]
+            .into_iter()
+            .filter(|sri| !sri.is_empty())
+            .map(|sri| format!("'sha384-{sri}'"))
+            .collect()
+    }
+
+    /// Integrity attributes for an entry file with the given SHA-384 digest
+    fn integrity_attributes(sri: &str) -> String {
+        if sri.is_empty() {
+            return String::new();
+        }
+
+        format!(" integrity=\"sha384-{sri}\" crossorigin=\"anonymous\"")
+    }
+
+    /// Script and link tags for the additional entrypoints, served from the static prefix
+    fn additional_tags(&self, static_prefix: &str) -> (String, String) {
+        let script_type = if self.module { " type=\"module\"" } else { "" };
+        let mut scripts = String::new();
+        let mut stylesheets = String::new();
+
+        for (js, css) in &self.additional {
+            scripts.push_str(&format!(
+                "<script{script_type} nonce=\"%CSP_NONCE%\" src=\"{static_prefix}/{js}\"></script>\n"
+            ));
+
+            if !css.is_empty() {
+                stylesheets.push_str(&format!(
+                    "<link rel=\"stylesheet\" nonce=\"%CSP_NONCE%\" href=\"{static_prefix}/{css}\">\n"
+                ));
+            }
+        }
+
+        (scripts, stylesheets)
+    }
+
+    /// Preload link tags of the entry files and the shared chunks, with the URL they preload
+    fn preload_tags(&self, static_prefix: &str, chunks: &[String]) -> Vec<(String, String)> {
+        let script = if self.module {
+            "rel=\"modulepreload\""
+        } else {
+            "rel=\"preload\" as=\"script\""
+        };
+
+        let scripts = std::iter::once(&self.js)
+            .chain(self.additional.iter().map(|(js, _)| js))
+            .chain(chunks)
+            .map(|js| (format!("{static_prefix}/{js}"), script));
+        let stylesheets = std::iter::once(&self.css)
+            .chain(self.additional.iter().map(|(_, css)| css))
+            .filter(|css| !css.is_empty())
+            .map(|css| {
+                (
+                    format!("{static_prefix}/{css}"),
+                    "rel=\"preload\" as=\"style\"",
+                )
+            });
+
+        scripts
+            .chain(stylesheets)
+            .map(|(href, rel)| {
+                let tag = format!("<link {rel} href=\"{href}\">");
+                (href, tag)
+            })
+            .collect()
+    }
+
+    /// `Link` header value to preload the entry files, module scripts are preloaded with `modulepreload`
+    fn preload_links(&self, static_prefix: &str) -> String {
+        let script = if self.module {
+            "rel=modulepreload"
+        } else {
+            "rel=preload; as=script"
+        };
+
+        let mut links = vec![format!("<{static_prefix}/{}>; {script}", self.js)];
+        if !self.css.is_empty() {
+            links.push(format!(
+                "<{static_prefix}/{}>; rel=preload; as=style",
+                self.css
+            ));
+        }
+
+        links.join(", ")
+    }
 }
 
-/// Entrypoint for the esbuild instance
-type EntryPoint = String;
+/// Entrypoints for the esbuild instance
+type EntryPoints = Vec<String>;
 
 /// Directory to serve the assets from
 type DistDir = String;
 
-/// Engine for serving assets, either proxy to an eslint instance or serve from memory
+/// Address (`host:port`) of an external dev server
+type Authority = String;
+
+/// Path prefix of the assets on an external dev server
+type BasePath = String;
+
+/// Engine for serving assets, either proxy to an eslint instance, serve the esbuild output from disk,
+/// proxy to an external dev server or serve from memory
 enum SpaxumEngine {
-    Proxy(EntryPoint, DistDir),
-    MemoryServe(EntryFiles, MemoryServe),
+    Proxy(EntryPoints, DistDir),
+    WatchDir(EntryPoints, PathBuf),
+    External(Authority, BasePath),
+    MemoryServe(EntryFiles, MemoryServe, &'static [Asset]),
+}
+
+/// Esbuild invocation of the dev server, see `Spaxum::start_proxy`
+struct EsbuildStart {
+    entrypoint_dir: Option<PathBuf>,
+    args: Vec<String>,
+    dist_dir: String,
+    serve: bool,
+}
+
+/// Routes of the engine that serves the assets, see `Spaxum::router`
+struct EngineRoutes {
+    /// Router of the assets, nested under the static prefix
+    static_router: Router,
+    health: MethodRouter<Arc<IndexState>>,
+    asset_manifest: MethodRouter<Arc<IndexState>>,
+    /// Inlines the CSS entry in the index.html, only for the assets served from memory
+    inline_css: Option<InlineCss>,
 }
 
 /// Spaxum instance, holds the page title and the statis asset engine
 pub struct Spaxum {
     title: String,
+    lang: Option<String>,
     engine: SpaxumEngine,
+    dev_script: String,
+    dev_stylesheet: String,
     esbuild_args: Vec<String>,
+    dev_esbuild_args: Vec<String>,
+    esbuild_target: Vec<String>,
+    esbuild_externals: Vec<String>,
+    esbuild_defines: Vec<String>,
+    esbuild_max_restarts: u32,
+    source_maps: bool,
+    esbuild_log_level: Option<LogLevel>,
+    esbuild_color: bool,
+    esbuild_log_quiet: bool,
+    proxy_host: IpAddr,
+    proxy_port: u16,
+    proxy_timeouts: ProxyTimeouts,
+    proxy_forward_headers: Option<Vec<HeaderName>>,
+    proxy_tls: Option<(PathBuf, PathBuf)>,
+    static_prefix: String,
+    bundle_static_prefix: Option<String>,
+    public_path: Option<String>,
+    base_href: Option<String>,
     html_template: Option<String>,
-    process_index: Option<Box<dyn Fn(String) -> String>>,
+    html_template_path: Option<PathBuf>,
+    template_vars: Vec<(String, String)>,
+    locale_templates: HashMap<String, String>,
+    head_html: Vec<String>,
+    meta: Vec<(MetaKey, String)>,
+    open_graph: Option<OpenGraph>,
+    process_index: Option<ProcessHtml>,
+    process_index_per_request: Option<ProcessHtmlPerRequest>,
+    csp_nonce: bool,
+    csp: Option<String>,
+    html_minification: Option<HtmlMinification>,
+    live_reload: bool,
+    dev_watch: bool,
+    dev_no_cache: bool,
+    health_route: Option<String>,
+    ready_route: bool,
+    asset_manifest_route: Option<String>,
+    error_pages: HashMap<StatusCode, String>,
+    pwa_manifest: Option<PwaManifest>,
+    service_worker: Option<String>,
+    service_worker_scope: Option<String>,
+    favicon: Option<Favicon>,
+    robots_txt: Option<String>,
+    asset_cache_control: Option<AssetCacheControl>,
+    response_headers: HeaderMap,
+    hsts: Option<HeaderValue>,
+    frame_policy: Option<FramePolicy>,
+    preload_hints: bool,
+    preload_entries: bool,
+    inline_css_threshold: Option<usize>,
+    dev_state: Arc<DevState>,
+}
+
+/// Favicon served at `/favicon.ico`
+enum Favicon {
+    /// Embedded favicon with its content type
+    Bytes(&'static [u8], &'static str),
+    /// Redirect to a static asset
+    Path(&'static str),
+    /// Embedded favicon with its content type and extension, also served from the static prefix
+    /// and linked in the index.html
+    Linked(&'static [u8], &'static str, &'static str),
+    /// Favicon file bundled by the build script, linked in the index.html
+    Bundled(&'static str),
+}
+
+impl Favicon {
+    /// Path of the static asset, relative paths are resolved against the static prefix
+    fn location(&self, static_prefix: &str) -> Option<String> {
+        let path = match self {
+            Favicon::Bytes(..) => return None,
+            Favicon::Path(path) | Favicon::Bundled(path) => path.to_string(),
+            Favicon::Linked(_, _, extension) => format!("favicon.{extension}"),
+        };
+
+        if path.starts_with('/') {
+            Some(path)
+        } else {
+            Some(format!("{static_prefix}/{path}"))
+        }
+    }
+
+    /// Content type, Cache-Control and bytes of an embedded favicon
+    fn embedded(&self) -> Option<(&'static str, &'static str, &'static [u8])> {
+        match *self {
+            Favicon::Bytes(bytes, mime) => Some((mime, FAVICON_BYTES_CACHE_CONTROL, bytes)),
+            Favicon::Linked(bytes, content_type, _) => {
+                Some((content_type, FAVICON_CACHE_CONTROL, bytes))
+            }
+            Favicon::Path(_) | Favicon::Bundled(_) => None,
+        }
+    }
+
+    /// Route that serves an embedded favicon
+    fn route<S>(&self) -> Option<MethodRouter<S>>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let (content_type, cache_control, bytes) = self.embedded()?;
+
+        Some(get(move || async move {
+            (
+                [(CONTENT_TYPE, content_type), (CACHE_CONTROL, cache_control)],
+                bytes,
+            )
+        }))
+    }
+
+    /// `<link rel="icon">` tag of the favicon, if it should be linked in the index.html
+    fn link_tag(&self, static_prefix: &str) -> Option<String> {
+        let content_type = match self {
+            Favicon::Bytes(..) | Favicon::Path(_) => return None,
+            Favicon::Linked(_, content_type, _) => *content_type,
+            Favicon::Bundled(file_name) => asset_manifest::content_type(Path::new(file_name)),
+        };
+
+        Some(format!(
+            "<link rel=\"icon\" type=\"{content_type}\" href=\"{}\">",
+            self.location(static_prefix)?
+        ))
+    }
+}
+
+/// Content type and extension of the supported favicon types
+fn favicon_type(mime: &str) -> Option<(&'static str, &'static str)> {
+    match mime {
+        "image/svg+xml" => Some(("image/svg+xml", "svg")),
+        "image/png" => Some(("image/png", "png")),
+        "image/x-icon" | "image/vnd.microsoft.icon" => Some(("image/x-icon", "ico")),
+        _ => None,
+    }
+}
+
+/// Meta tag of the index.html, identified by its `name` or `property` attribute
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MetaKey {
+    Name(String),
+    Property(String),
 }
 
+impl MetaKey {
+    /// Render the meta tag with the (escaped) content
+    fn render(&self, content: &str) -> String {
+        let (attribute, key) = match self {
+            MetaKey::Name(name) => ("name", name),
+            MetaKey::Property(property) => ("property", property),
+        };
+
+        format!(
+            "<meta {attribute}=\"{}\" content=\"{}\">",
+            escape_html(key),
+            escape_html(content)
+        )
+    }
+}
+
+/// Escape text for use in HTML content and quoted attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Async hook to process the index.html before serving it
+type ProcessHtml =
+    Box<dyn Fn(String) -> Pin<Box<dyn Future<Output = String> + Send>> + Send + Sync>;
+
+/// Hook to process the index.html for every request, with the head of the request
+type ProcessHtmlPerRequest = Box<dyn Fn(String, &Parts) -> String + Send + Sync>;
+
+/// State of the index page handler
+/// The process html hook is resolved on the first request, the result is served for all requests
+/// When the template is read from a file in development, it is rendered and processed for every request
+struct IndexState {
+    html: String,
+    template_path: Option<PathBuf>,
+    replacements: Replacements,
+    process_index: Option<ProcessHtml>,
+    process_index_per_request: Option<ProcessHtmlPerRequest>,
+    html_minification: Option<HtmlMinification>,
+    csp_nonce: bool,
+    csp: Option<String>,
+    rendered: OnceCell<String>,
+    locales: HashMap<String, LocaleIndex>,
+    inline_css: Option<InlineCss>,
+}
+
+/// Index page of a locale, see `Spaxum::add_locale`
+struct LocaleIndex {
+    html: String,
+    rendered: OnceCell<String>,
+}
+
+impl IndexState {
+    /// Inline the CSS entry, run the process html hook and minify the result
+    async fn process(&self, html: String) -> String {
+        let html = match &self.inline_css {
+            Some(inline_css) => inline_css.apply(html).await,
+            None => html,
+        };
+
+        let html = match &self.process_index {
+            Some(process_index) => process_index(html).await,
+            None => html,
+        };
+
+        match self.html_minification {
+            Some(html_minification) => html_minification.minify(&html),
+            None => html,
+        }
+    }
+}
+
+/// Ordered placeholder replacements that render the index.html from the HTML template
+#[derive(Debug, Default)]
+struct Replacements(Vec<(String, String)>);
+
+impl Replacements {
+    /// Add a replacement, it is applied after the replacements added before
+    fn replace(&mut self, from: impl Into<String>, to: impl Into<String>) -> &mut Self {
+        self.0.push((from.into(), to.into()));
+
+        self
+    }
+
+    /// Render the template
+    fn render(&self, template: &str) -> String {
+        let mut html = template.to_string();
+        for (from, to) in &self.0 {
+            html = html.replace(from, to);
+        }
+
+        html
+    }
+}
+
+/// Built-in HTML template
+const INDEX_HTML: &str = include_str!("../index.html");
+
+/// Default path prefix to serve the static assets from
+const STATIC_PREFIX: &str = "/static";
+
+/// Robots.txt that allows crawling everything, see `Spaxum::with_robots_txt`
+pub const DEFAULT_ROBOTS_TXT: &str = "User-agent: *\nDisallow:\n";
+
+/// Path browsers request the favicon from
+const FAVICON_ROUTE: &str = "/favicon.ico";
+
+/// Cache-Control of the favicon set with `Spaxum::set_favicon`, a week
+const FAVICON_CACHE_CONTROL: &str = "public, max-age=604800";
+
+/// Cache-Control of the favicon set with `Spaxum::with_favicon`, a day
+const FAVICON_BYTES_CACHE_CONTROL: &str = "public, max-age=86400";
+
+/// Cross-origin isolation headers, see `Spaxum::with_cross_origin_isolation`
+const CROSS_ORIGIN_EMBEDDER_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-embedder-policy");
+const CROSS_ORIGIN_OPENER_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-opener-policy");
+const CROSS_ORIGIN_RESOURCE_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-resource-policy");
+
+/// Output file of the service worker in the dist directory
+const SERVICE_WORKER_FILE: &str = "sw.js";
+
 const ESBUILD_OPTIONS: &[&str] = &[
-    "--color=false",
     "--asset-names=[name]",
-    "--public-path=/static/",
     "--loader:.png=file",
     "--loader:.jpg=file",
     "--loader:.jpeg=file",
@@ -65,8 +535,6 @@ const ESBUILD_OPTIONS: &[&str] = &[
     "--loader:.gif=file",
 ];
 
-const ESBUILD_DEV_SERVER: &str = "127.0.0.1:8888";
-
 /// Load the assets from the memory or proxy to an esbuild instance
 /// Returns a Spaxum instance that can be used to create an axum router
 #[macro_export]
@@ -79,6 +547,12 @@ macro_rules! load {
             let dist_dir = Path::new(concat!(env!("OUT_DIR"), "/dist"));
 
             spaxum::Spaxum::new_proxy($title, entrypoint, dist_dir)
+                .set_esbuild_defines(spaxum::parse_defines(
+                    option_env!("SPAXUM_ESBUILD_DEFINES").unwrap_or_default(),
+                ))
+                .set_bundle_static_prefix(option_env!("SPAXUM_STATIC_PATH").unwrap_or("/static"))
+                .set_base_href(option_env!("SPAXUM_BASE_HREF").unwrap_or_default())
+                .set_bundled_favicon(option_env!("SPAXUM_FAVICON"))
         } else {
             let assets: &[Asset] = include!(concat!(env!("OUT_DIR"), "/spaxum.rs"));
 
@@ -89,9 +563,21 @@ macro_rules! load {
                 css: option_env!("SPAXUM_CSS_ENTRY")
                     .unwrap_or_default()
                     .to_string(),
+                js_sri: option_env!("SPAXUM_JS_SRI").unwrap_or_default().to_string(),
+                css_sri: option_env!("SPAXUM_CSS_SRI")
+                    .unwrap_or_default()
+                    .to_string(),
+                additional: spaxum::EntryFiles::parse_entries(
+                    option_env!("SPAXUM_ADDITIONAL_ENTRIES").unwrap_or_default(),
+                ),
+                module: option_env!("SPAXUM_JS_FORMAT") == Some("esm"),
             };
 
             spaxum::Spaxum::new($title, assets, entry_files)
+                .set_bundle_static_prefix(option_env!("SPAXUM_STATIC_PATH").unwrap_or("/static"))
+                .set_base_href(option_env!("SPAXUM_BASE_HREF").unwrap_or_default())
+                .set_public_path(option_env!("SPAXUM_PUBLIC_PATH").unwrap_or_default())
+                .set_bundled_favicon(option_env!("SPAXUM_FAVICON"))
         }
     }};
 }
@@ -102,18 +588,31 @@ impl Spaxum {
     pub fn new(title: &str, assets: &'static [Asset], entry_files: EntryFiles) -> Self {
         let memory_serve = MemoryServe::new(assets);
 
-        Self {
-            title: title.to_string(),
-            esbuild_args: Vec::new(),
-            engine: SpaxumEngine::MemoryServe(entry_files, memory_serve),
-            process_index: None,
-            html_template: None,
-        }
+        Self::with_engine(
+            title,
+            SpaxumEngine::MemoryServe(entry_files, memory_serve, assets),
+        )
     }
 
     /// Create a new Spaxum instance, with the page title, entrypoint and dist directory
     /// Uses esbuild to bundle the assets and serve them in development mode
+    /// Multiple entrypoints can be passed as a `;` separated list, see `new_proxy_multiple`
+    /// When the `SPAXUM_DEV_SERVER` environment variable is set, requests are proxied to that dev server instead
     pub fn new_proxy(title: &str, entrypoint: &str, dist_dir: &Path) -> Self {
+        let entrypoints = entrypoint.split(';').collect::<Vec<&str>>();
+
+        Self::new_proxy_multiple(title, &entrypoints, dist_dir)
+    }
+
+    /// Create a new Spaxum instance, with the page title, entrypoints and dist directory
+    /// Uses esbuild to bundle the assets and serve them in development mode
+    /// The first entrypoint is used for `%SCRIPT%` and `%STYLESHEET%`, the others are added to the HTML,
+    /// the outputs are named after the entrypoint files, e.g. "admin.tsx" is served as "admin.js" and "admin.css"
+    pub fn new_proxy_multiple(title: &str, entrypoints: &[&str], dist_dir: &Path) -> Self {
+        if let Ok(url) = std::env::var("SPAXUM_DEV_SERVER") {
+            return Self::new_external_proxy(title, &url);
+        }
+
         // cleanup and ignore if directory is already empty
         let _ = std::fs::remove_dir_all(dist_dir);
 
@@ -121,441 +620,2949 @@ impl Spaxum {
             panic!("Invalid path provided by OUT_DIR");
         };
 
+        Self::with_engine(
+            title,
+            SpaxumEngine::Proxy(
+                entrypoints
+                    .iter()
+                    .map(|entrypoint| entrypoint.to_string())
+                    .collect(),
+                dist_dir.into(),
+            ),
+        )
+    }
+
+    /// Create a new Spaxum instance, with the page title and the URL of an externally managed dev server
+    /// Requests for static assets are proxied to the dev server (e.g. "http://localhost:5173"), esbuild is not started
+    /// The script and stylesheet names can be set with `set_dev_entry_files`
+    pub fn new_external_proxy(title: &str, url: &str) -> Self {
+        let Some((authority, base_path)) = parse_dev_server_url(url) else {
+            panic!("Invalid dev server URL: {url}");
+        };
+
+        Self::with_engine(title, SpaxumEngine::External(authority, base_path))
+    }
+
+    /// Create a new Spaxum instance with default settings for the given engine
+    fn with_engine(title: &str, engine: SpaxumEngine) -> Self {
         Self {
             title: title.to_string(),
+            lang: None,
             esbuild_args: Vec::new(),
-            engine: SpaxumEngine::Proxy(entrypoint.into(), dist_dir.into()),
+            dev_esbuild_args: Vec::new(),
+            esbuild_target: Vec::new(),
+            esbuild_externals: Vec::new(),
+            esbuild_defines: Vec::new(),
+            esbuild_max_restarts: ESBUILD_MAX_RESTARTS,
+            source_maps: false,
+            esbuild_log_level: None,
+            esbuild_color: false,
+            esbuild_log_quiet: false,
+            proxy_host: ESBUILD_DEV_HOST,
+            proxy_port: dev_port_from_env(),
+            proxy_timeouts: ProxyTimeouts::default(),
+            proxy_forward_headers: None,
+            proxy_tls: None,
+            static_prefix: STATIC_PREFIX.to_string(),
+            bundle_static_prefix: None,
+            public_path: None,
+            base_href: None,
+            engine,
+            dev_script: "index.js".to_string(),
+            dev_stylesheet: "index.css".to_string(),
             process_index: None,
+            process_index_per_request: None,
             html_template: None,
+            html_template_path: None,
+            template_vars: Vec::new(),
+            locale_templates: HashMap::new(),
+            head_html: Vec::new(),
+            meta: Vec::new(),
+            open_graph: None,
+            csp_nonce: false,
+            csp: None,
+            html_minification: None,
+            live_reload: true,
+            dev_watch: true,
+            dev_no_cache: true,
+            health_route: None,
+            ready_route: false,
+            asset_manifest_route: None,
+            error_pages: HashMap::new(),
+            pwa_manifest: None,
+            service_worker: None,
+            service_worker_scope: None,
+            favicon: None,
+            robots_txt: None,
+            asset_cache_control: None,
+            response_headers: HeaderMap::new(),
+            hsts: None,
+            frame_policy: None,
+            preload_hints: false,
+            preload_entries: false,
+            inline_css_threshold: None,
+            dev_state: Arc::new(DevState::default()),
         }
     }
 
-    pub fn start_proxy(&self) {
-        let (entrypoint, dist_dir) = match &self.engine {
-            SpaxumEngine::Proxy(entrypoint, dist_dir) => (entrypoint, dist_dir),
-            _ => panic!("Invalid engine type"),
-        };
-
-        let esbuild = get_esbuild_path();
-
-        let Ok(mut child) = Command::new(esbuild)
-            .args([
-                entrypoint,
-                "--bundle",
-                format!("--outdir={dist_dir}").as_str(),
-                "--watch=forever",
-                format!("--servedir={dist_dir}").as_str(),
-                format!("--serve={ESBUILD_DEV_SERVER}").as_str(),
-                "--entry-names=index",
-            ])
-            .args(ESBUILD_OPTIONS)
-            .args(&self.esbuild_args)
-            .kill_on_drop(true)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-        else {
-            panic!("esbuild failed to start");
-        };
-
-        tokio::spawn(async move {
-            let stdout = child
-                .stdout
-                .take()
-                .expect("esbuild did not have a handle to stdout");
-            let mut stdout_reader = tokio::io::BufReader::new(stdout).lines();
-
-            let stderr = child
-                .stderr
-                .take()
-                .expect("esbuild did not have a handle to stderr");
-            let mut stderr_reader = tokio::io::BufReader::new(stderr).lines();
+    /// Arguments of the esbuild command in development, esbuild serves the dist directory itself if `serve` is set
+    /// The dev-only arguments of `set_dev_esbuild_args` are only added while watching the sources
+    fn esbuild_dev_args(&self, entrypoints: &[String], dist_dir: &str, serve: bool) -> Vec<String> {
+        let mut args: Vec<String> = entrypoints.to_vec();
+        args.extend([
+            "--bundle".to_string(),
+            format!("--outdir={dist_dir}"),
+            format!("--public-path={}/", self.asset_prefix()),
+        ]);
+
+        if self.dev_watch {
+            args.push("--watch=forever".to_string());
+        }
 
-            loop {
-                tokio::select! {
-                    next_line = stdout_reader.next_line() => {
-                        if let Ok(Some(line)) = next_line {
-                            println!("esbuild: {line}");
-                        } else {
-                            eprintln!("esbuild: stdout closed");
-                            break;
-                        }
-                    },
-                    next_error_line = stderr_reader.next_line() => {
-                        if let Ok(Some(line)) = next_error_line {
-                            eprintln!("esbuild: {line}");
-                        } else {
-                            eprintln!("esbuild: stderr closed");
-                            break;
-                        }
-                    },
-                    process_result = child.wait() => {
-                        match process_result {
-                            Ok(exit_status) => {
-                                if exit_status.success() {
-                                    println!("esbuild process exited successfully");
-                                } else {
-                                    eprintln!("esbuild process exited with error");
-                                    break;
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("esbuild process failed to exit: {e}");
-                                break;
-                            }
-                        }
-                    }
-                }
+        // multiple entrypoints keep their own names and share code through chunks, like `bundle_multiple`
+        if entrypoints.len() > 1 {
+            args.extend([
+                "--entry-names=[name]".to_string(),
+                "--format=esm".to_string(),
+                "--splitting".to_string(),
+            ]);
+        } else {
+            args.push("--entry-names=index".to_string());
+        }
+
+        // without the esbuild dev server the files are served from the dist directory by spaxum
+        if serve {
+            args.push(format!("--servedir={dist_dir}"));
+            args.push(format!(
+                "--serve={}",
+                SocketAddr::new(self.proxy_host, self.proxy_port)
+            ));
+
+            if let Some((cert, key)) = &self.proxy_tls {
+                args.push(format!("--certfile={}", cert.to_string_lossy()));
+                args.push(format!("--keyfile={}", key.to_string_lossy()));
             }
-        });
-    }
+        }
 
-    /// Set the HTML page title
-    pub fn set_title(mut self, title: impl Into<String>) -> Self {
-        self.title = title.into();
+        args.extend(ESBUILD_OPTIONS.iter().map(|option| option.to_string()));
+        args.push(format!("--color={}", self.esbuild_color));
 
-        self
-    }
+        if let Some(log_level) = self.esbuild_log_level {
+            args.push(format!("--log-level={}", log_level.as_str()));
+        }
 
-    /// Set the process index function, this function is called before serving the index.html
-    /// This can be used to process the index.html before serving it
-    pub fn set_process_html(mut self, process_index: impl Fn(String) -> String + 'static) -> Self {
-        self.process_index = Some(Box::new(process_index));
+        args.extend(
+            self.esbuild_defines
+                .iter()
+                .map(|define| format!("--define:{define}")),
+        );
 
-        self
-    }
+        if !self.esbuild_target.is_empty() {
+            args.push(format!("--target={}", self.esbuild_target.join(",")));
+        }
 
-    /// Set the HTML template, this template is used to render the index.html
-    pub fn set_html_template(mut self, html_template: impl Into<String>) -> Self {
-        self.html_template = Some(html_template.into());
+        args.extend(
+            self.esbuild_externals
+                .iter()
+                .map(|external| format!("--external:{external}")),
+        );
 
-        self
-    }
+        if self.source_maps {
+            args.push("--sourcemap=inline".to_string());
+        }
 
-    /// Set additional esbuild arguments, these arguments are passed to the esbuild instance
-    pub fn set_esbuild_args(mut self, args: Vec<String>) -> Self {
-        self.esbuild_args = args;
+        args.extend(self.esbuild_args.iter().cloned());
 
-        self
+        // dev-only arguments are not used when bundling once, like in the release build
+        if self.dev_watch {
+            args.extend(self.dev_esbuild_args.iter().cloned());
+        }
+
+        args
     }
 
-    /// Get the memory serve instance, this can de used to fine-tune memory serve settings
-    pub fn memory_serve(&self) -> Option<&MemoryServe> {
-        match &self.engine {
-            SpaxumEngine::MemoryServe(_, memory_serve) => Some(memory_serve),
-            _ => None,
+    /// Start esbuild in watch mode, this is done by `router` so it is only needed to start esbuild earlier
+    /// Returns `None` if esbuild was already started, if the engine does not use esbuild, or if watching
+    /// is disabled with `set_dev_watch`, then esbuild bundles once before this returns
+    /// On a multi-threaded runtime the worker thread is handed off while probing esbuild and bundling,
+    /// see `start_proxy_async`
+    /// Returns an error if esbuild is not found, is too old, fails to start or fails to bundle
+    pub fn start_proxy(&self) -> Result<Option<SpaxumDevHandle>, SpaxumError> {
+        let Some(start) = self.prepare_esbuild() else {
+            return Ok(None);
+        };
+
+        let (esbuild, version) = block_in_place(|| probe_esbuild(start.entrypoint_dir.as_deref()))
+            .map_err(SpaxumError::Esbuild)?;
+        eprintln!("spaxum: using esbuild {version}, {esbuild}");
+
+        if !self.dev_watch {
+            let output = block_in_place(|| esbuild.command().args(&start.args).output());
+            self.bundled_once(output)?;
+
+            return Ok(None);
         }
+
+        self.supervise_esbuild(esbuild, start).map(Some)
     }
 
-    /// Get the axum router for the Spaxum instance, serves static assets (from the "/static" path)
-    pub fn router<S>(self) -> Router<S>
-    where
-        S: Clone + Send + Sync + 'static,
-    {
-        let html = match self.html_template {
-            Some(ref html) => html,
-            None => include_str!("../index.html"),
+    /// Start esbuild like `start_proxy`, without blocking the async runtime while probing esbuild
+    /// and bundling once, `into_router` starts esbuild with this
+    pub async fn start_proxy_async(&self) -> Result<Option<SpaxumDevHandle>, SpaxumError> {
+        let Some(start) = self.prepare_esbuild() else {
+            return Ok(None);
         };
 
-        let mut html = html.replace("%TITLE%", &self.title);
-
-        match self.engine {
-            SpaxumEngine::MemoryServe(entry_files, memory_serve) => {
-                html = html
-                    .replace("%SCRIPT%", &entry_files.js)
-                    .replace("%STYLESHEET%", &entry_files.css);
+        let entrypoint_dir = start.entrypoint_dir.clone();
+        let (esbuild, version) =
+            tokio::task::spawn_blocking(move || probe_esbuild(entrypoint_dir.as_deref()))
+                .await
+                .map_err(|e| SpaxumError::Esbuild(format!("unable to probe esbuild: {e}")))?
+                .map_err(SpaxumError::Esbuild)?;
+        eprintln!("spaxum: using esbuild {version}, {esbuild}");
+
+        if !self.dev_watch {
+            let output = tokio::process::Command::from(esbuild.command())
+                .args(&start.args)
+                .output()
+                .await;
+            self.bundled_once(output)?;
+
+            return Ok(None);
+        }
 
-                if let Some(process_index) = self.process_index {
-                    html = process_index(html);
-                }
+        self.supervise_esbuild(esbuild, start).map(Some)
+    }
 
-                Router::new()
-                    .nest("/static", memory_serve.into_router())
-                    .fallback(Html(html))
+    /// Mark esbuild as started and configure the dev state, `None` if esbuild was already started
+    /// or the engine does not use esbuild
+    fn prepare_esbuild(&self) -> Option<EsbuildStart> {
+        let (entrypoints, dist_dir, serve) = match &self.engine {
+            SpaxumEngine::Proxy(entrypoints, dist_dir) => (entrypoints, dist_dir.clone(), true),
+            SpaxumEngine::WatchDir(entrypoints, dist_dir) => {
+                (entrypoints, dist_dir.to_string_lossy().to_string(), false)
             }
-            _ => {
-                self.start_proxy();
-
-                let live_reload = include_str!("../live_reload.html");
+            _ => return None,
+        };
 
-                html = html
-                    .replace("%SCRIPT%", "index.js")
-                    .replace("%STYLESHEET%", "index.css")
-                    .replace("</body>", &format!("{live_reload}</body>"));
+        if !self.dev_state.start() {
+            return None;
+        }
 
-                if let Some(process_index) = self.process_index {
-                    html = process_index(html);
-                }
+        self.dev_state.set_log_quiet(self.esbuild_log_quiet);
+        self.dev_state.set_serving(serve);
 
-                let client: Client =
-                    hyper_util::client::legacy::Client::<(), ()>::builder(TokioExecutor::new())
-                        .build(HttpConnector::new());
+        // above the info level esbuild does not log that it is listening, nor the finished builds
+        self.dev_state.set_track_output(
+            self.esbuild_log_level
+                .is_none_or(|level| level <= LogLevel::Info),
+        );
 
-                let proxy_router = Router::new()
-                    .fallback(get(proxy_handler))
-                    .with_state(client);
+        Some(EsbuildStart {
+            // a node_modules directory is looked up from the first entrypoint
+            entrypoint_dir: entrypoints
+                .first()
+                .and_then(|entrypoint| Path::new(entrypoint).parent())
+                .map(Path::to_path_buf),
+            args: self.esbuild_dev_args(entrypoints, &dist_dir, serve),
+            dist_dir,
+            serve,
+        })
+    }
 
-                Router::new()
-                    .nest("/static", proxy_router)
-                    .fallback(Html(html))
-            }
+    /// Spawn esbuild in watch mode, and a task that restarts it when it exits
+    fn supervise_esbuild(
+        &self,
+        esbuild: Esbuild,
+        start: EsbuildStart,
+    ) -> Result<SpaxumDevHandle, SpaxumError> {
+        let EsbuildStart {
+            args,
+            dist_dir,
+            serve,
+            ..
+        } = start;
+
+        let mut child = spawn_esbuild(esbuild.command(), &args)
+            .map_err(|e| SpaxumError::Esbuild(format!("esbuild failed to start: {e}")))?;
+
+        // without the esbuild dev server the browsers are reloaded when the dist directory changes
+        if !serve {
+            tokio::spawn(watch_dist_dir(
+                PathBuf::from(&dist_dir),
+                self.dev_state.clone(),
+                self.dev_state.shutdown_receiver(),
+            ));
         }
-    }
-}
 
-/// Proxy handler for development mode, proxies requests to the esbuild dev server
-async fn proxy_handler(
-    State(client): State<Client>,
-    mut req: Request,
-) -> Result<Response, StatusCode> {
-    use axum::response::IntoResponse;
+        let dev_state = self.dev_state.clone();
+        let max_restarts = self.esbuild_max_restarts;
 
-    let path = req.uri().path();
-    let path_query = req
-        .uri()
-        .path_and_query()
-        .map(|v| v.as_str())
-        .unwrap_or(path);
+        // supervise esbuild, restart it with the same arguments when it exits
+        let mut shutdown = self.dev_state.shutdown_receiver();
+        self.dev_state.set_running(true);
 
-    let uri = format!("http://{ESBUILD_DEV_SERVER}{path_query}");
+        let task = tokio::spawn(async move {
+            let mut restarts = 0;
+            let mut crashes: Vec<tokio::time::Instant> = Vec::new();
 
-    let Ok(uri) = Uri::try_from(uri) else {
-        return Err(StatusCode::BAD_REQUEST);
-    };
+            loop {
+                watch_esbuild(child, &dev_state, &mut shutdown).await;
 
-    *req.uri_mut() = uri;
-    // esbuild validates the Host header, so rewrite it for the internal proxy hop.
-    req.headers_mut()
-        .insert(HOST, HeaderValue::from_static(ESBUILD_DEV_SERVER));
+                if *shutdown.borrow() {
+                    break;
+                }
 
-    Ok(client
-        .request(req)
-        .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?
-        .into_response())
-}
+                // esbuild keeps crashing, e.g. because of an invalid argument, restarting will not help
+                let now = tokio::time::Instant::now();
+                crashes.retain(|crash| now.duration_since(*crash) < ESBUILD_CRASH_WINDOW);
+                crashes.push(now);
+
+                if crashes.len() >= ESBUILD_CRASH_LIMIT {
+                    eprintln!(
+                        "esbuild: exited {} times within {ESBUILD_CRASH_WINDOW:?}, giving up, restart the server to continue",
+                        crashes.len()
+                    );
+                    break;
+                }
 
-/// Esbuild manifest output structure
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct Output {
-    bytes: usize,
-    css_bundle: Option<String>,
-    entry_point: Option<String>,
-}
+                if restarts >= max_restarts {
+                    eprintln!(
+                        "esbuild: giving up after {restarts} restarts, restart the server to continue"
+                    );
+                    break;
+                }
 
-/// Esbuild manifest structure
-#[derive(Debug, Deserialize, Serialize)]
-struct Manifest {
-    outputs: HashMap<String, Output>,
-}
+                tokio::select! {
+                    _ = tokio::time::sleep(ESBUILD_RESTART_BACKOFF) => {},
+                    _ = shutdown_requested(&mut shutdown) => break,
+                }
 
-impl EntryFiles {
-    /// Get the entry files from the esbuild manifest file
-    fn from_manifest(manifest_file: &str, entrypoint: &Path) -> Option<Self> {
-        let manifest_str =
-            std::fs::read_to_string(manifest_file).expect("Unable to read manifest file.");
-
-        let manifest: Manifest =
-            serde_json::from_str(&manifest_str).expect("Unmable to parse manifest file.");
-
-        for (name, output) in manifest.outputs.iter() {
-            if let Some(js) = output.entry_point.as_ref()
-                && entrypoint.to_string_lossy().ends_with(js)
-            {
-                return Some(EntryFiles {
-                    js: Path::new(name)
-                        .file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string(),
-                    css: output
-                        .css_bundle
-                        .as_ref()
-                        .and_then(|f| {
-                            Some(Path::new(&f).file_name()?.to_string_lossy().to_string())
-                        })
-                        .unwrap_or_default(),
-                });
+                restarts += 1;
+                dev_state.restarted();
+                eprintln!("esbuild: restarting ({restarts}/{max_restarts})");
+
+                child = match spawn_esbuild(esbuild.command(), &args) {
+                    Ok(child) => child,
+                    Err(e) => {
+                        eprintln!("esbuild: failed to restart: {e}");
+                        break;
+                    }
+                };
             }
-        }
 
-        None
-    }
-}
+            dev_state.set_running(false);
+        });
 
-/// Error macro for build scripts
-macro_rules! error {
-    ($s:expr) => {
-        println!("cargo::error={}", $s);
-        exit(1);
-    };
+        Ok(SpaxumDevHandle {
+            task,
+            dev_state: self.dev_state.clone(),
+        })
+    }
 
-    ($s:expr, $($v:tt)*) => {
-        println!("cargo::error={}", format!($s, $($v)*));
-        exit(1);
-    };
-}
+    /// Report the result of bundling once, an error with the esbuild output if the build failed
+    fn bundled_once(
+        &self,
+        output: std::io::Result<std::process::Output>,
+    ) -> Result<(), SpaxumError> {
+        let output =
+            output.map_err(|e| SpaxumError::Esbuild(format!("esbuild failed to start: {e}")))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        if !output.status.success() {
+            return Err(SpaxumError::Esbuild(format!(
+                "esbuild failed to bundle ({}):\n{stdout}{stderr}",
+                output.status
+            )));
+        }
 
-/// Get the path to the esbuild executable
-/// Optionally use esbuild binary shipped with spaxum, fallback the system esbuild
-fn get_esbuild_path() -> PathBuf {
-    if !cfg!(target_arch = "x86_64") {
-        return PathBuf::from("esbuild");
-    }
+        for line in stdout.lines().chain(stderr.lines()) {
+            eprintln!("esbuild: {line}");
+        }
 
-    let current_dir = Path::new(file!()).parent().and_then(|p| p.parent());
+        self.dev_state.build_finished();
+        self.dev_state.set_ready(true);
 
-    match current_dir {
-        Some(p) => {
-            let esbuild = p.join("esbuild");
+        Ok(())
+    }
 
-            if esbuild.exists() {
-                esbuild
-            } else {
-                PathBuf::from("esbuild")
-            }
+    /// Start esbuild for the router, an error is logged and reported by the ready route and the
+    /// error overlay, `into_router` returns it instead
+    fn start_esbuild_for_router(&self) {
+        // a no-op if esbuild was already started
+        if let Err(e) = self.start_proxy() {
+            eprintln!("spaxum: {e}");
+            self.dev_state.start_failed(e.to_string());
         }
-        None => PathBuf::from("esbuild"),
     }
-}
 
-/// File name to write asset metadata to
-const ASSET_FILE: &str = "spaxum.rs";
-
-/// Write the asset metadata to a file
-fn write_asset_file(out_dir: &Path, code: &str) {
-    let target = out_dir.join(ASSET_FILE);
-    match std::fs::write(&target, code) {
-        Ok(_) => {}
-        Err(e) => {
-            error!(
-                "Unable to write asset file: {} {e:?}",
-                target.to_string_lossy()
-            );
+    /// Get a handle to stop the esbuild dev server, e.g. on graceful shutdown of the axum server
+    /// `kill_on_drop` only stops esbuild when the runtime is torn down, the handle stops it explicitly
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            dev_state: self.dev_state.clone(),
         }
     }
-}
 
-/// Bundle the assets using release compilation with esbuild
-/// Pass the entrypoint to the runtime for debug builds
-pub fn bundle(entrypoint: &str) {
-    bundle_with_args(entrypoint, &[]);
-}
+    /// Get a guard that stops the esbuild dev server when dropped, hold it in `main`
+    /// The esbuild process is also stopped on `std::process::exit` and when the main thread panics,
+    /// the guard covers other ways of leaving `main` in which the runtime is not dropped
+    pub fn cleanup_guard(&self) -> CleanupGuard {
+        CleanupGuard { _private: () }
+    }
 
-/// Bundle the assets using release compilation with esbuild
-/// Pass the entrypoint to the runtime for debug builds
-/// Optionally pass additional arguments to esbuild
-pub fn bundle_with_args(entrypoint: &str, build_args: &[&str]) {
-    // Log messages to cargo
-    fn log(msg: &str) {
-        if std::env::var("SPAXUM_QUIET") != Ok("1".to_string()) {
-            println!("cargo::warning={}", msg);
-        }
+    /// Call the callback after every esbuild build in development, e.g. to generate code after a successful build
+    /// The callback runs on a blocking task, so it does not hold up the esbuild output
+    pub fn on_rebuild(self, on_rebuild: impl Fn(RebuildEvent) + Send + Sync + 'static) -> Self {
+        self.dev_state.set_on_rebuild(Arc::new(on_rebuild));
+
+        self
     }
 
-    // Check if the entrypoint exists
-    let Ok(entrypoint) = Path::new(&entrypoint).canonicalize() else {
-        error!("{} not found!", entrypoint);
-    };
+    /// Get the status of the esbuild process, e.g. to report it on a health page or to check
+    /// that esbuild is running in integration tests
+    /// The status is empty when the engine does not use esbuild or esbuild was not started yet
+    pub fn dev_status(&self) -> DevStatus {
+        self.dev_state.status()
+    }
 
-    // Get the OUT_DIR environment variable, this is where we store compressed assets and asset metadata code
-    let Some(out_dir) = env::var_os("OUT_DIR") else {
-        error!("OUT_DIR not set!");
-    };
+    /// Set the HTML page title
+    pub fn set_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
 
-    // Create neccesary paths and their string variants
-    let out_dir = Path::new(&out_dir);
-    let dist_dir = out_dir.join("dist");
-    let dist_dir_str = dist_dir.to_string_lossy();
-    let entrypoint_str = entrypoint.to_string_lossy();
-    let manifest_file = out_dir.join("manifest.json");
-    let manifest_file_str = manifest_file.to_string_lossy();
-
-    // Skip bundling in debug mode, assets will be served by the esbuild dev server
-    if cfg!(debug_assertions) {
-        println!("cargo::rustc-env=SPAXUM_ENTRYPOINT={entrypoint_str}");
-        write_asset_file(out_dir, "&[]");
-        log("Skipping bundling in debug mode, assets will be served by the esbuild dev server.");
-        exit(0);
-    }
-
-    // Cleanup and ignore if directory is already empty
-    let _ = std::fs::remove_dir_all(&dist_dir);
-
-    // Determine the directory of the entrypoint file, and rerun the build if it changes
-    let Some(source_dir) = entrypoint.parent() else {
-        error!(
-            "Unable to get parent directory of entrypoint: {}",
-            entrypoint_str
-        );
-    };
+        self
+    }
 
-    // Rerun build script if source directory changes
-    println!("cargo::rerun-if-changed={}", source_dir.to_string_lossy());
-
-    log(&format!("Bundling {entrypoint_str} using esbuild..."));
-
-    // Bundle assets using esbuild
-    let esbuild = get_esbuild_path();
-    let Ok(mut child) = std::process::Command::new(esbuild)
-        .args([
-            "--bundle",
-            &entrypoint_str,
-            &format!("--outfile={dist_dir_str}/index.js"),
-            &format!("--metafile={manifest_file_str}"),
-            "--entry-names=[name]-[hash]",
-            "--minify",
-        ])
-        .args(ESBUILD_OPTIONS)
-        .args(build_args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-    else {
-        error!("esbuild failed to start");
-    };
+    /// Set the process index function, this function is called before serving the index.html
+    /// This can be used to process the index.html before serving it
+    /// The returned future is resolved once, on the first request for the index.html
+    pub fn set_process_html(
+        mut self,
+        process_index: impl Fn(String) -> Pin<Box<dyn Future<Output = String> + Send>>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.process_index = Some(Box::new(process_index));
 
-    if let Some(ref mut stdout) = child.stdout {
-        for line in std::io::BufReader::new(stdout).lines() {
-            let line = line.unwrap();
-            log(&format!("esbuild: {line}"));
-        }
+        self
     }
 
-    if let Some(ref mut stderr) = child.stderr {
-        for line in std::io::BufReader::new(stderr).lines() {
-            let line = line.unwrap();
-            log(&format!("esbuild error: {line}"));
-        }
+    /// Set the per request process index function, called with the index.html and the head of the request
+    /// This can be used to add request specific content, e.g. a CSRF token, the `set_process_html`
+    /// function and the minification run before it
+    /// With `with_csp_nonce` the nonce is already substituted, the hook gets it as the `CspNonce` request extension
+    pub fn set_process_html_per_request(
+        mut self,
+        process_index: impl Fn(String, &Parts) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.process_index_per_request = Some(Box::new(process_index));
+
+        self
     }
 
-    let Ok(status) = child.wait() else {
-        error!("esbuild failed to bundle: {entrypoint_str}");
-    };
+    /// Set the HTML template, this template is used to render the index.html
+    pub fn set_html_template(mut self, html_template: impl Into<String>) -> Self {
+        self.html_template = Some(html_template.into());
+        self.html_template_path = None;
 
-    // Log errors if esbuild fails
-    if !status.success() {
-        error!("esbuild failed to bundle: {entrypoint_str}");
+        self
     }
 
-    // Log success message
-    log("esbuild completed successfully");
+    /// Read the HTML template from a file, this template is used to render the index.html
+    /// In development the file is read for every request, so template changes show up without recompiling
+    /// When serving from memory the file is read once, the built-in template is used if it cannot be read
+    pub fn set_html_template_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.html_template_path = Some(path.into());
+        self.html_template = None;
 
-    // read contents of manifest_file as string
-    let Some(entry_point) = EntryFiles::from_manifest(&manifest_file_str, &entrypoint) else {
-        error!(
-            "Unable to find entrypoint in manifest file: {}",
-            manifest_file_str
-        );
-    };
+        self
+    }
+
+    /// Set the language of the index.html, e.g. `nl`, the `%LANG%` placeholder defaults to `en`
+    /// A template without the placeholder gets the language set on its `<html>` element
+    pub fn set_lang(mut self, lang: impl Into<String>) -> Self {
+        self.lang = Some(lang.into());
 
-    // Set environment variables for the entrypoint files
-    println!("cargo::rustc-env=SPAXUM_JS_ENTRY={}", entry_point.js);
-    println!("cargo::rustc-env=SPAXUM_CSS_ENTRY={}", entry_point.css);
+        self
+    }
 
-    // Convert assets to code and write to file
-    let code = memory_serve::assets_to_code(&dist_dir_str, &dist_dir, out_dir, true, log);
+    /// Add an HTML template for a locale, e.g. `fr` or `en-US`, served to browsers that prefer the locale
+    /// The locale is picked from the `Accept-Language` header of the request, the regular template is
+    /// served if no locale matches, the placeholders are replaced like in the regular template,
+    /// except `%LANG%` which is the locale
+    pub fn add_locale(mut self, locale: &str, template: impl Into<String>) -> Self {
+        self.locale_templates
+            .insert(locale.to_ascii_lowercase(), template.into());
 
-    write_asset_file(out_dir, &code);
+        self
+    }
+
+    /// Set a template variable, every `%KEY%` placeholder in the HTML template is replaced by the value
+    /// The built-in placeholders (`%TITLE%`, `%SCRIPT%`, `%STYLESHEET%`, ...) take precedence
+    /// Variables are replaced in the order they are first set, so a value can contain the placeholder
+    /// of a variable that is set later, setting a variable again only changes its value
+    pub fn set_template_var(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let (key, value) = (key.into(), value.into());
+
+        match self.template_vars.iter_mut().find(|(var, _)| *var == key) {
+            Some((_, var_value)) => *var_value = value,
+            None => self.template_vars.push((key, value)),
+        }
+
+        self
+    }
+
+    /// Add HTML to the `<head>` of the index.html, e.g. meta tags or preconnects, multiple calls accumulate
+    /// The HTML replaces the `%HEAD%` placeholder, or is inserted before `</head>` if the template has none
+    pub fn add_head_html(mut self, html: impl Into<String>) -> Self {
+        self.head_html.push(html.into());
+
+        self
+    }
+
+    /// Add a `<meta name="..." content="...">` tag to the index.html, the values are escaped
+    /// Adding a name again replaces the content, the tags are rendered before the `add_head_html` HTML
+    pub fn add_meta(self, name: impl Into<String>, content: impl Into<String>) -> Self {
+        self.set_meta(MetaKey::Name(name.into()), content.into())
+    }
+
+    /// Add a `<meta property="..." content="...">` tag to the index.html, e.g. for OpenGraph
+    /// Adding a property again replaces the content
+    pub fn add_meta_property(
+        self,
+        property: impl Into<String>,
+        content: impl Into<String>,
+    ) -> Self {
+        self.set_meta(MetaKey::Property(property.into()), content.into())
+    }
+
+    /// Set the description meta tag of the index.html
+    pub fn set_description(self, description: impl Into<String>) -> Self {
+        self.add_meta("description", description)
+    }
+
+    /// Add the Open Graph and Twitter card meta tags for link previews to the index.html
+    /// Meta tags added with `add_meta` and `add_meta_property` take precedence
+    pub fn set_open_graph(mut self, open_graph: OpenGraph) -> Self {
+        self.open_graph = Some(open_graph);
+
+        self
+    }
+
+    /// Add or replace a meta tag
+    fn set_meta(mut self, key: MetaKey, content: String) -> Self {
+        match self.meta.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, existing)) => *existing = content,
+            None => self.meta.push((key, content)),
+        }
+
+        self
+    }
+
+    /// Generate a random nonce for every index.html response, to use a nonce based Content-Security-Policy
+    /// Every `%CSP_NONCE%` placeholder in the HTML is replaced by the nonce, this includes the nonce attributes
+    /// of the injected script and stylesheet tags, and the
+    /// `Content-Security-Policy: script-src 'self' 'nonce-<nonce>'` header is added to the response
+    pub fn with_csp_nonce(self) -> Self {
+        self.set_csp_nonce(true)
+    }
+
+    /// Enable or disable the CSP nonce of the index.html responses, see `with_csp_nonce`
+    /// Disabled by default, the nonce attributes are removed from the HTML when disabled
+    pub fn set_csp_nonce(mut self, csp_nonce: bool) -> Self {
+        self.csp_nonce = csp_nonce;
+
+        self
+    }
+
+    /// Send the Content-Security-Policy with every response
+    /// With `with_csp_nonce` the `CSP_NONCE` sentinel is replaced by the nonce of the index.html response,
+    /// and removed from the other responses
+    pub fn with_csp(self, csp: CspBuilder) -> Self {
+        self.with_csp_policy(csp.build())
+    }
+
+    /// Send the Content-Security-Policy with every response, like `with_csp` with a policy string
+    /// e.g. `"script-src 'self' %CSP_NONCE%; object-src 'none'"`
+    pub fn with_csp_policy(mut self, policy: impl Into<String>) -> Self {
+        self.csp = Some(policy.into());
+
+        self
+    }
+
+    /// Minify the index.html, removes comments, collapses whitespace between tags and
+    /// trims attribute values, the contents of script, style and pre elements are kept as is
+    pub fn with_html_minification(mut self) -> Self {
+        self.html_minification = Some(HtmlMinification::Simple);
+
+        self
+    }
+
+    /// Minify the index.html using the `minify-html` crate, this also minifies inline CSS
+    /// and omits optional quotes and attributes
+    #[cfg(feature = "minify-html")]
+    pub fn with_aggressive_html_minification(mut self) -> Self {
+        self.html_minification = Some(HtmlMinification::Aggressive);
+
+        self
+    }
+
+    /// Serve a health check at the given path, e.g. "/healthz", responding with 200 and "ok"
+    /// In development the health check fails with 503 if the esbuild dev server does not respond
+    pub fn with_health_route(mut self, path: impl Into<String>) -> Self {
+        self.health_route = Some(path.into());
+
+        self
+    }
+
+    /// Serve a JSON list of the static assets at the given path, e.g. "/api/assets"
+    /// Every asset has a `path`, `size` and `content_type`, in development the dist directory is listed
+    pub fn with_asset_manifest_route(mut self, path: impl Into<String>) -> Self {
+        self.asset_manifest_route = Some(path.into());
+
+        self
+    }
+
+    /// Set the HTML page served for error responses with the given status code, e.g. `StatusCode::NOT_FOUND`
+    /// Applies to the static asset routes, in development a 404 page hints that the asset might not be bundled yet
+    pub fn set_error_page(mut self, status: StatusCode, html: impl Into<String>) -> Self {
+        self.error_pages.insert(status, html.into());
+
+        self
+    }
+
+    /// Serve a web app manifest at `/manifest.json` and link it in the index.html
+    /// Relative icon paths are served from the static prefix, include the icon files with `include_pwa_icons`
+    pub fn with_pwa_manifest(mut self, manifest: PwaManifest) -> Self {
+        self.pwa_manifest = Some(manifest);
+
+        self
+    }
+
+    /// Serve the service worker at `sw_path`, e.g. `/sw.js`, and register it in the index.html
+    /// Bundle the service worker entrypoint with `bundle_service_worker` in the build script
+    pub fn with_service_worker(mut self, sw_path: impl Into<String>) -> Self {
+        self.service_worker = Some(sw_path.into());
+
+        self
+    }
+
+    /// Set the scope of the service worker registration, defaults to the directory of the service worker path
+    pub fn register_sw_scope(mut self, scope: &str) -> Self {
+        self.service_worker_scope = Some(scope.to_string());
+
+        self
+    }
+
+    /// Serve the favicon at `/favicon.ico`, instead of the index.html, cached for a day
+    pub fn with_favicon(mut self, bytes: &'static [u8], mime: &'static str) -> Self {
+        self.favicon = Some(Favicon::Bytes(bytes, mime));
+
+        self
+    }
+
+    /// Redirect `/favicon.ico` to a static asset, a relative path is relative to the static prefix
+    pub fn with_favicon_path(mut self, path: &'static str) -> Self {
+        self.favicon = Some(Favicon::Path(path));
+
+        self
+    }
+
+    /// Serve the favicon at `/favicon.ico` and `<static prefix>/favicon.<extension>`, cached for a week,
+    /// and add a `<link rel="icon">` tag to the index.html
+    /// Panics if the type is not `image/svg+xml`, `image/png` or `image/x-icon`
+    pub fn set_favicon(mut self, bytes: &'static [u8], mime: &str) -> Self {
+        let Some((content_type, extension)) = favicon_type(mime) else {
+            panic!("Unsupported favicon type {mime}, use image/svg+xml, image/png or image/x-icon");
+        };
+
+        self.favicon = Some(Favicon::Linked(bytes, content_type, extension));
+
+        self
+    }
+
+    /// Set the favicon file bundled by the build script with `include_favicon`, used by the `load!` macro
+    /// A favicon set before is kept
+    pub fn set_bundled_favicon(mut self, file_name: Option<&'static str>) -> Self {
+        if let Some(file_name) = file_name
+            && self.favicon.is_none()
+        {
+            self.favicon = Some(Favicon::Bundled(file_name));
+        }
+
+        self
+    }
+
+    /// Serve `/robots.txt` with the given content, instead of the index.html
+    /// Pass `DEFAULT_ROBOTS_TXT` to allow crawling everything
+    pub fn with_robots_txt(mut self, content: impl Into<String>) -> Self {
+        self.robots_txt = Some(content.into());
+
+        self
+    }
+
+    /// Serve `<static prefix>/__spaxum/ready` in development, it responds with `200 OK` once esbuild is
+    /// listening and finished its first build, and `503 Service Unavailable` with the state otherwise
+    /// For end-to-end tests that wait for the assets, the route is absent when serving from memory
+    pub fn with_ready_route(mut self) -> Self {
+        self.ready_route = true;
+
+        self
+    }
+
+    /// Enable cross-origin isolation, e.g. for `SharedArrayBuffer`, by adding the `Cross-Origin-Embedder-Policy`,
+    /// `Cross-Origin-Opener-Policy` and `Cross-Origin-Resource-Policy` headers to every response
+    /// This may break third-party iframes and cross-origin resources that do not opt in with CORP or CORS
+    pub fn with_cross_origin_isolation(mut self) -> Self {
+        self.response_headers.insert(
+            CROSS_ORIGIN_EMBEDDER_POLICY,
+            HeaderValue::from_static("require-corp"),
+        );
+        self.response_headers.insert(
+            CROSS_ORIGIN_OPENER_POLICY,
+            HeaderValue::from_static("same-origin"),
+        );
+        self.response_headers.insert(
+            CROSS_ORIGIN_RESOURCE_POLICY,
+            HeaderValue::from_static("same-origin"),
+        );
+
+        self
+    }
+
+    /// Send the `Strict-Transport-Security` header with every response, for HTTPS-only deployments
+    /// The header is not sent in development, the esbuild dev server and external dev servers use HTTP
+    pub fn with_hsts(
+        mut self,
+        max_age_seconds: u64,
+        include_subdomains: bool,
+        preload: bool,
+    ) -> Self {
+        let mut hsts = format!("max-age={max_age_seconds}");
+        if include_subdomains {
+            hsts.push_str("; includeSubDomains");
+        }
+        if preload {
+            hsts.push_str("; preload");
+        }
+
+        self.hsts = HeaderValue::try_from(hsts).ok();
+
+        self
+    }
+
+    /// Add preload link tags for the entry scripts and stylesheets to the head of the index.html,
+    /// and for the shared chunks of ES module builds, links already in the template are not duplicated
+    pub fn set_preload_entries(mut self, preload_entries: bool) -> Self {
+        self.preload_entries = preload_entries;
+
+        self
+    }
+
+    /// Embed the CSS entry in a `<style>` element instead of linking it, if it is smaller than the threshold
+    /// Only applies to assets served from memory, the CSS is read on the first request for the index.html
+    /// With a Content-Security-Policy, allow the style element with a `style-src` nonce source
+    pub fn set_inline_css(mut self, threshold_bytes: usize) -> Self {
+        self.inline_css_threshold = Some(threshold_bytes);
+
+        self
+    }
+
+    /// Send a `Link` header with the index.html, so browsers start fetching the entry script and
+    /// stylesheet before parsing the HTML
+    pub fn with_preload_hints(mut self) -> Self {
+        self.preload_hints = true;
+
+        self
+    }
+
+    /// Send the `X-Frame-Options` header with every response, to protect against clickjacking
+    /// With `with_csp` the equivalent `frame-ancestors` directive is added to the Content-Security-Policy,
+    /// unless the policy has a `frame-ancestors` directive
+    pub fn with_frame_options(mut self, policy: FramePolicy) -> Self {
+        self.frame_policy = Some(policy);
+
+        self
+    }
+
+    /// Set the `Cross-Origin-Embedder-Policy` header of every response, e.g. `credentialless`
+    /// Panics if the value is not a valid header value
+    pub fn with_cross_origin_embedder_policy(mut self, value: &str) -> Self {
+        let Ok(value) = HeaderValue::from_str(value) else {
+            panic!("Invalid Cross-Origin-Embedder-Policy: {value}");
+        };
+
+        self.response_headers
+            .insert(CROSS_ORIGIN_EMBEDDER_POLICY, value);
+
+        self
+    }
+
+    /// Set the browser targets for the esbuild dev server, e.g. `["chrome100", "firefox110"]`
+    /// Targets follow the esbuild target syntax, use `bundle_with_targets` for release builds
+    pub fn set_esbuild_target(mut self, targets: Vec<String>) -> Self {
+        self.esbuild_target = targets;
+
+        self
+    }
+
+    /// Set the packages the esbuild dev server does not bundle, e.g. `["react"]`
+    /// Use `bundle_with_externals` with the same packages for release builds
+    pub fn set_esbuild_externals(mut self, externals: Vec<String>) -> Self {
+        self.esbuild_externals = externals;
+
+        self
+    }
+
+    /// Set the esbuild defines (`name=value`) for the esbuild dev server
+    /// The `load!` macro sets the defines of the build script helpers, e.g. `define_from_cargo_feature`
+    pub fn set_esbuild_defines(mut self, defines: Vec<String>) -> Self {
+        self.esbuild_defines = defines;
+
+        self
+    }
+
+    /// Set the maximum number of times esbuild is restarted when it exits (development only), defaults to 5
+    pub fn set_esbuild_max_restarts(mut self, max_restarts: u32) -> Self {
+        self.esbuild_max_restarts = max_restarts;
+
+        self
+    }
+
+    /// Send `Cache-Control: no-store` for the assets of the esbuild dev server (development only), enabled by default
+    /// Browsers then always fetch the latest build instead of a cached asset
+    pub fn set_dev_no_cache(mut self, no_cache: bool) -> Self {
+        self.dev_no_cache = no_cache;
+
+        self
+    }
+
+    /// Set the esbuild log level in development, esbuild logs at the info level by default
+    /// Above the info level esbuild does not log the finished builds, so live reload and the
+    /// build error overlay do not work
+    pub fn set_esbuild_log_level(mut self, level: LogLevel) -> Self {
+        self.esbuild_log_level = Some(level);
+
+        self
+    }
+
+    /// Enable or disable colored esbuild output in development, disabled by default
+    pub fn set_esbuild_color(mut self, color: bool) -> Self {
+        self.esbuild_color = color;
+
+        self
+    }
+
+    /// Only print the errors and warnings of esbuild in development, errors are never hidden
+    pub fn set_esbuild_log_quiet(mut self, quiet: bool) -> Self {
+        self.esbuild_log_quiet = quiet;
+
+        self
+    }
+
+    /// Generate inline source maps in development, so the browser devtools show the original sources
+    /// For release builds use `bundle_with_source_maps`
+    pub fn with_source_maps(mut self) -> Self {
+        self.source_maps = true;
+
+        self
+    }
+
+    /// Enable or disable live reloading in development, enabled by default
+    /// When disabled the live reload script is not injected and the reload endpoint is not served
+    /// Has no effect when serving assets from memory
+    pub fn set_live_reload(mut self, live_reload: bool) -> Self {
+        self.live_reload = live_reload;
+
+        self
+    }
+
+    /// Set additional esbuild arguments, these arguments are passed to the esbuild instance
+    /// They apply whenever spaxum runs esbuild: the dev server, serving from disk and bundling once,
+    /// pass the same arguments to `bundle_with_args` for release builds
+    pub fn set_esbuild_args(mut self, args: Vec<String>) -> Self {
+        self.esbuild_args = args;
+
+        self
+    }
+
+    /// Set esbuild arguments that only apply while esbuild watches the sources in development,
+    /// e.g. `--define:__DEV__=true`, they are not used when bundling once with `set_dev_watch(false)`
+    pub fn set_dev_esbuild_args(mut self, args: Vec<String>) -> Self {
+        self.dev_esbuild_args = args;
+
+        self
+    }
+
+    /// Set the port of the esbuild dev server (development only)
+    /// Defaults to the `SPAXUM_DEV_PORT` environment variable, or 8888 if it is not set
+    pub fn set_proxy_port(mut self, port: u16) -> Self {
+        self.proxy_port = port;
+
+        self
+    }
+
+    /// Set the address the esbuild dev server binds to (development only), defaults to 127.0.0.1
+    /// Accepts an IP address, e.g. "0.0.0.0" to reach esbuild from outside a Docker container,
+    /// or an address with a port, e.g. "0.0.0.0:8888", which also sets the port
+    pub fn set_proxy_bind(mut self, addr: impl Into<String>) -> Self {
+        let addr = addr.into();
+
+        if let Ok(addr) = addr.parse::<SocketAddr>() {
+            self.proxy_host = addr.ip();
+            self.proxy_port = addr.port();
+        } else if let Ok(ip) = addr.parse::<IpAddr>() {
+            self.proxy_host = ip;
+        } else {
+            panic!(
+                "Invalid esbuild bind address \"{addr}\", use an IP address (\"0.0.0.0\") or an IP address and port (\"0.0.0.0:8888\")"
+            );
+        }
+
+        self
+    }
+
+    /// Address the proxy connects to, the loopback address if esbuild binds to all interfaces
+    fn esbuild_upstream(&self) -> SocketAddr {
+        let host = match self.proxy_host {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            host => host,
+        };
+
+        SocketAddr::new(host, self.proxy_port)
+    }
+
+    /// Serve the esbuild output directly from the dist directory instead of proxying to the esbuild dev server
+    /// Esbuild only watches the sources, no port is used, this has no effect for the other engines
+    /// Live reload follows the changes of the dist directory, also at esbuild log levels above info
+    pub fn set_dev_serve_from_disk(mut self, serve_from_disk: bool) -> Self {
+        self.engine = match self.engine {
+            SpaxumEngine::Proxy(entrypoints, dist_dir) if serve_from_disk => {
+                SpaxumEngine::WatchDir(entrypoints, PathBuf::from(dist_dir))
+            }
+            SpaxumEngine::WatchDir(entrypoints, dist_dir) if !serve_from_disk => {
+                SpaxumEngine::Proxy(entrypoints, dist_dir.to_string_lossy().to_string())
+            }
+            engine => engine,
+        };
+
+        self
+    }
+
+    /// Disable watching the sources in development, esbuild bundles once when the router is created
+    /// The output is served from the dist directory like `set_dev_serve_from_disk`, without live reload
+    /// The router panics with the esbuild output if the build fails, e.g. for smoke tests in CI
+    pub fn set_dev_watch(mut self, watch: bool) -> Self {
+        self.dev_watch = watch;
+
+        if watch {
+            self
+        } else {
+            self.set_dev_serve_from_disk(true)
+        }
+    }
+
+    /// Set the script and stylesheet names requested from an external dev server, relative to the static prefix
+    /// Defaults to "index.js" and "index.css", pass an empty stylesheet if the script loads the styles
+    pub fn set_dev_entry_files(mut self, script: &str, stylesheet: &str) -> Self {
+        self.dev_script = script.to_string();
+        self.dev_stylesheet = stylesheet.to_string();
+
+        self
+    }
+
+    /// Set the time to wait for the esbuild dev server to respond (development only)
+    /// Defaults to 30 seconds, requests that time out get a 504 response, event streams are not affected
+    /// The default is finite so a hung esbuild does not keep browser requests waiting, pass `Duration::MAX` to wait forever
+    pub fn set_proxy_timeout(mut self, timeout: Duration) -> Self {
+        self.proxy_timeouts.response = timeout;
+
+        self
+    }
+
+    /// Only forward the given request headers to the dev server (development only)
+    /// All request headers are forwarded by default, e.g. pass `vec![ACCEPT, ACCEPT_ENCODING]` to restrict them
+    /// The `Host`, `X-Forwarded-*` and websocket upgrade headers are always set by the proxy
+    pub fn set_proxy_forward_headers(mut self, headers: Vec<HeaderName>) -> Self {
+        self.proxy_forward_headers = Some(headers);
+
+        self
+    }
+
+    /// Serve the esbuild dev server over HTTPS with the given certificate and key (PEM), requires the `tls` feature
+    /// The proxy only accepts this certificate, so a self-signed certificate can be used
+    #[cfg(feature = "tls")]
+    pub fn set_proxy_tls(mut self, cert: PathBuf, key: PathBuf) -> Self {
+        self.proxy_tls = Some((cert, key));
+
+        self
+    }
+
+    /// Set the time to wait for a connection to the esbuild dev server (development only)
+    /// There is no connect timeout by default, requests that fail to connect in time get a 502 response
+    pub fn set_proxy_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.proxy_timeouts.connect = Some(timeout);
+
+        self
+    }
+
+    /// Set the time to retry requests for the entry assets while esbuild finishes its first build (development only)
+    /// GET requests for the entry scripts and stylesheets that fail to connect or respond with 404 are
+    /// retried until the budget is spent, defaults to 5 seconds, `Duration::ZERO` disables retrying
+    pub fn set_proxy_retry_budget(mut self, budget: Duration) -> Self {
+        self.proxy_timeouts.retry = budget;
+
+        self
+    }
+
+    /// Set the path prefix the static assets are served from, defaults to "/static"
+    /// In development the esbuild public path follows the prefix, for release builds set the same path
+    /// with `set_static_path` in the build script, the `load!` macro then sets the prefix as well
+    pub fn set_static_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        let prefix = prefix.trim_matches('/');
+
+        if prefix.is_empty() {
+            panic!("The static prefix can not be empty or \"/\"");
+        }
+
+        self.static_prefix = format!("/{prefix}");
+
+        self
+    }
+
+    /// Set the URL the index.html loads the assets from when serving from memory, e.g. `https://cdn.example.com/assets`
+    /// The assets are still served from the static prefix, use `set_public_path` in the build script,
+    /// the `load!` macro passes it on, so the URLs in the bundle match; an empty path is ignored
+    pub fn set_public_path(mut self, path: impl Into<String>) -> Self {
+        let path = path.into();
+        let path = path.trim_end_matches('/');
+
+        self.public_path = (!path.is_empty()).then(|| path.to_string());
+
+        self
+    }
+
+    /// Set the path the application is served from behind a reverse proxy, e.g. `/portal/`
+    /// Adds a `<base href>` element to the index.html and prefixes the asset URLs, the routes are not prefixed,
+    /// the proxy strips the path; an empty path or `/` serves the application from the root
+    /// For release builds set the `SPAXUM_BASE_HREF` environment variable when building, the `load!` macro
+    /// passes it on, so the URLs in the bundle match
+    pub fn set_base_href(mut self, base_href: impl Into<String>) -> Self {
+        self.base_href = normalize_base_href(&base_href.into());
+
+        self
+    }
+
+    /// Path prefix of the asset URLs in the index.html and the bundle
+    /// The assets of release builds can be loaded from a CDN, in development they are served locally
+    fn asset_prefix(&self) -> String {
+        match (&self.public_path, &self.engine) {
+            (Some(public_path), SpaxumEngine::MemoryServe(..)) => public_path.clone(),
+            _ => format!("{}{}", self.base_path(), self.static_prefix),
+        }
+    }
+
+    /// Base href without the trailing slash, empty if the application is served from the root
+    fn base_path(&self) -> &str {
+        self.base_href
+            .as_deref()
+            .map_or("", |base_href| base_href.trim_end_matches('/'))
+    }
+
+    /// Set the path prefix the assets were bundled for by the build script, used by the `load!` macro
+    /// Also sets the static prefix, a different prefix set afterwards is reported when creating the router
+    pub fn set_bundle_static_prefix(self, prefix: &str) -> Self {
+        let mut spaxum = self.set_static_prefix(prefix);
+        spaxum.bundle_static_prefix = Some(spaxum.static_prefix.clone());
+
+        spaxum
+    }
+
+    /// Set the Cache-Control header of the static assets served from memory, in release builds
+    /// Assets with a content hash in the file name are cached for a year and immutable, even if `max_age` is shorter
+    pub fn set_asset_cache_control(mut self, max_age: u32, immutable: bool) -> Self {
+        self.asset_cache_control = Some(AssetCacheControl { max_age, immutable });
+
+        self
+    }
+
+    /// Get the memory serve instance, this can de used to fine-tune memory serve settings
+    pub fn memory_serve(&self) -> Option<&MemoryServe> {
+        match &self.engine {
+            SpaxumEngine::MemoryServe(_, memory_serve, _) => Some(memory_serve),
+            _ => None,
+        }
+    }
+
+    /// Output file names of the esbuild entrypoints in development
+    fn dev_entry_files(&self) -> EntryFiles {
+        let entrypoints = match &self.engine {
+            SpaxumEngine::Proxy(entrypoints, _) | SpaxumEngine::WatchDir(entrypoints, _) => {
+                entrypoints.as_slice()
+            }
+            _ => &[],
+        };
+
+        let mut entries = entrypoints
+            .iter()
+            .map(|entrypoint| {
+                let name = Path::new(entrypoint)
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+
+                (format!("{name}.js"), format!("{name}.css"))
+            })
+            .collect::<Vec<(String, String)>>();
+
+        // a single entrypoint is always named index
+        if entries.len() <= 1 {
+            entries = vec![("index.js".to_string(), "index.css".to_string())];
+        }
+
+        let (js, css) = entries.remove(0);
+
+        EntryFiles {
+            js,
+            css,
+            js_sri: String::new(),
+            css_sri: String::new(),
+            module: !entries.is_empty(),
+            additional: entries,
+        }
+    }
+
+    /// Render the esbuild output in the index.html and create the routes for the build errors and live reload
+    fn esbuild_dev_router<T>(&self, replacements: &mut Replacements) -> Router<T>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let entry_files = self.dev_entry_files();
+        let (scripts, stylesheets) = entry_files.additional_tags(&self.asset_prefix());
+        let script_type = if entry_files.module {
+            "module"
+        } else {
+            "text/javascript"
+        };
+
+        replacements
+            .replace("%SCRIPT%", &entry_files.js)
+            .replace("%SCRIPT_TYPE%", script_type)
+            .replace("%STYLESHEET%", &entry_files.css)
+            .replace("%INTEGRITY_SCRIPT%", "")
+            .replace("%INTEGRITY_STYLESHEET%", "")
+            .replace("</head>", format!("{stylesheets}</head>"))
+            .replace("</body>", format!("{scripts}</body>"));
+
+        let mut dev_router = Router::new().route("/__spaxum/error", get(build_error_handler));
+
+        if self.ready_route {
+            dev_router = dev_router.route("/__spaxum/ready", get(ready_handler));
+        }
+
+        // without watching there are no rebuilds to reload
+        if self.live_reload && self.dev_watch {
+            let live_reload = include_str!("../live_reload.html");
+            replacements.replace("</body>", format!("{live_reload}</body>"));
+
+            dev_router = dev_router.route("/__spaxum/reload", get(reload_handler));
+        }
+
+        dev_router.with_state(self.dev_state.clone())
+    }
+
+    /// Replacements of the title, the language, the base URL and the head HTML of a template
+    /// The language of a locale template is its locale
+    fn head_replacements(&self, template: &str, locale: Option<&str>) -> Replacements {
+        let mut replacements = Replacements::default();
+        replacements.replace("%TITLE%", &self.title);
+
+        let lang = locale.or(self.lang.as_deref());
+        if template.contains("%LANG%") {
+            replacements.replace("%LANG%", escape_html(lang.unwrap_or("en")));
+        } else if let Some(lang) = lang {
+            match html_lang_replacement(template, lang) {
+                Some((html_tag, with_lang)) => {
+                    replacements.replace(html_tag, with_lang);
+                }
+                None => eprintln!(
+                    "spaxum: the HTML template has no %LANG% placeholder and no <html> element, the language is not set"
+                ),
+            }
+        }
+
+        // before any element with a URL
+        if let Some(base_href) = &self.base_href {
+            replacements.replace(
+                "<head>",
+                format!("<head>\n  <base href=\"{}\">", escape_html(base_href)),
+            );
+        }
+
+        let open_graph_tags = match &self.open_graph {
+            Some(open_graph) => {
+                let assets = match &self.engine {
+                    SpaxumEngine::MemoryServe(_, _, assets) => *assets,
+                    _ => &[],
+                };
+
+                open_graph.meta_tags(&self.title, assets, &self.asset_prefix())
+            }
+            None => Vec::new(),
+        };
+
+        let head_html = self
+            .meta
+            .iter()
+            .chain(
+                open_graph_tags
+                    .iter()
+                    .filter(|(key, _)| !self.meta.iter().any(|(meta, _)| meta == key)),
+            )
+            .map(|(key, content)| key.render(content))
+            .chain(self.head_html.iter().cloned())
+            .collect::<String>();
+        if template.contains("%HEAD%") {
+            replacements.replace("%HEAD%", head_html);
+        } else if !head_html.is_empty() {
+            eprintln!(
+                "spaxum: the HTML template has no %HEAD% placeholder, the head HTML is inserted before </head>"
+            );
+            replacements.replace("</head>", format!("{head_html}</head>"));
+        }
+
+        replacements
+    }
+
+    /// Serve the assets embedded in the binary, with their ETags and byte ranges
+    fn memory_serve_routes(
+        &self,
+        entry_files: &EntryFiles,
+        memory_serve: MemoryServe,
+        assets: &'static [Asset],
+        replacements: &mut Replacements,
+    ) -> EngineRoutes {
+        let asset_prefix = self.asset_prefix();
+        let (scripts, stylesheets) = entry_files.additional_tags(&asset_prefix);
+        let script_type = if entry_files.module {
+            "module"
+        } else {
+            "text/javascript"
+        };
+
+        replacements
+            .replace("%SCRIPT%", &entry_files.js)
+            .replace("%SCRIPT_TYPE%", script_type)
+            .replace("%STYLESHEET%", &entry_files.css)
+            .replace(
+                "%INTEGRITY_SCRIPT%",
+                EntryFiles::integrity_attributes(&entry_files.js_sri),
+            )
+            .replace(
+                "%INTEGRITY_STYLESHEET%",
+                EntryFiles::integrity_attributes(&entry_files.css_sri),
+            )
+            .replace("</head>", format!("{stylesheets}</head>"))
+            .replace("</body>", format!("{scripts}</body>"));
+
+        // the embedded assets do not change, so the manifest is computed once
+        let asset_manifest = asset_manifest::memory_assets(assets, &self.static_prefix);
+
+        let memory_router = match self.asset_cache_control {
+            Some(cache_control) => memory_serve.into_router().layer(from_fn_with_state(
+                cache_control,
+                asset_cache_control_handler,
+            )),
+            None => memory_serve.into_router(),
+        };
+
+        // the ETags are computed when bundling, browsers revalidate assets without downloading them again
+        // range requests are answered from the whole response, the ETag is needed for `If-Range`
+        let memory_router = memory_router
+            .layer(from_fn_with_state(asset_etags(assets), asset_etag_handler))
+            .layer(from_fn(range_handler));
+
+        let inline_css = self
+            .inline_css_threshold
+            .filter(|_| !entry_files.css.is_empty())
+            .map(|threshold_bytes| InlineCss {
+                router: memory_router.clone(),
+                route: format!("/{}", entry_files.css),
+                href: format!("{asset_prefix}/{}", entry_files.css),
+                threshold_bytes,
+                nonce: self.csp_nonce,
+            });
+
+        EngineRoutes {
+            static_router: memory_router,
+            health: get(health_handler),
+            asset_manifest: get(move || async move { Json(asset_manifest) }),
+            inline_css,
+        }
+    }
+
+    /// Proxy the assets to the esbuild dev server, esbuild is started if it is not running yet
+    fn proxy_routes(&self, dist_dir: PathBuf, replacements: &mut Replacements) -> EngineRoutes {
+        self.start_esbuild_for_router();
+
+        let static_prefix = self.static_prefix.clone();
+        let entry_files = self.dev_entry_files();
+        let retry_paths = std::iter::once((entry_files.js, entry_files.css))
+            .chain(entry_files.additional)
+            .flat_map(|(js, css)| [format!("/{js}"), format!("/{css}")])
+            .collect();
+        let proxy_state = ProxyState::new(
+            self.esbuild_upstream(),
+            self.proxy_timeouts,
+            self.dev_state.clone(),
+        )
+        .with_forward_headers(self.proxy_forward_headers.clone())
+        .with_retry_paths(retry_paths)
+        .with_no_cache(self.dev_no_cache);
+
+        #[cfg(feature = "tls")]
+        let proxy_state = match &self.proxy_tls {
+            Some((cert, _)) => proxy_state.with_tls(cert, self.proxy_timeouts),
+            None => proxy_state,
+        };
+
+        EngineRoutes {
+            static_router: self
+                .esbuild_dev_router(replacements)
+                .fallback(proxy_handler)
+                .with_state(proxy_state.clone()),
+            health: get(dev_health_handler).with_state(proxy_state),
+            asset_manifest: get(move || async move {
+                Json(asset_manifest::dist_assets(&dist_dir, &static_prefix))
+            }),
+            inline_css: None,
+        }
+    }
+
+    /// Serve the esbuild output from the dist directory, esbuild is started if it is not running yet
+    fn watch_dir_routes(
+        &self,
+        dist_dir: Arc<PathBuf>,
+        replacements: &mut Replacements,
+    ) -> EngineRoutes {
+        self.start_esbuild_for_router();
+
+        let static_prefix = self.static_prefix.clone();
+
+        EngineRoutes {
+            static_router: self
+                .esbuild_dev_router(replacements)
+                .fallback(dist_file_handler)
+                .with_state(dist_dir.clone()),
+            health: get(health_handler),
+            asset_manifest: get(move || async move {
+                Json(asset_manifest::dist_assets(&dist_dir, &static_prefix))
+            }),
+            inline_css: None,
+        }
+    }
+
+    /// Proxy the assets to an external dev server, like Vite
+    fn external_routes(
+        &self,
+        authority: Authority,
+        base_path: BasePath,
+        replacements: &mut Replacements,
+    ) -> EngineRoutes {
+        // external dev servers serve ES modules and reload the page themselves
+        replacements
+            .replace("%SCRIPT%", &self.dev_script)
+            .replace("%SCRIPT_TYPE%", "module")
+            .replace("%STYLESHEET%", &self.dev_stylesheet)
+            .replace("%INTEGRITY_SCRIPT%", "")
+            .replace("%INTEGRITY_STYLESHEET%", "");
+
+        // the external dev server is managed by the user, so it is assumed to be listening
+        self.dev_state.set_ready(true);
+
+        let proxy_state = ProxyState::external(
+            authority,
+            base_path,
+            self.proxy_timeouts,
+            self.dev_state.clone(),
+        )
+        .with_forward_headers(self.proxy_forward_headers.clone());
+
+        EngineRoutes {
+            static_router: Router::new()
+                .fallback(proxy_handler)
+                .with_state(proxy_state.clone()),
+            health: get(dev_health_handler).with_state(proxy_state),
+            // the assets of an external dev server are not known
+            asset_manifest: get(|| async { Json(Vec::<asset_manifest::AssetInfo>::new()) }),
+            inline_css: None,
+        }
+    }
+
+    /// Preload the entry files and the chunks they import, unless the template already preloads them
+    fn preload_replacements(
+        &self,
+        replacements: &mut Replacements,
+        template: &str,
+        entry_files: &EntryFiles,
+        chunks: &[String],
+    ) {
+        let html = replacements.render(template);
+        let preloaded = preloaded_hrefs(&html);
+
+        let preload_tags = entry_files
+            .preload_tags(&self.asset_prefix(), chunks)
+            .into_iter()
+            .filter(|(href, _)| !preloaded.contains(&href.as_str()))
+            .map(|(_, tag)| tag)
+            .collect::<String>();
+
+        // before the stylesheet and script tags, the charset has to be declared first
+        if html.contains("</title>") {
+            replacements.replace("</title>", format!("</title>{preload_tags}"));
+        } else {
+            replacements.replace("</head>", format!("{preload_tags}</head>"));
+        }
+    }
+
+    /// Replacements of the favicon, PWA manifest and service worker tags, the static prefix,
+    /// the template variables and the nonces, after the engine specific replacements
+    fn page_replacements(&self, replacements: &mut Replacements, pwa_manifest: bool) {
+        let base_path = self.base_path();
+
+        if let Some(link_tag) = self
+            .favicon
+            .as_ref()
+            .and_then(|favicon| favicon.link_tag(&format!("{base_path}{}", self.static_prefix)))
+        {
+            replacements.replace("</head>", format!("{link_tag}</head>"));
+        }
+
+        if pwa_manifest {
+            replacements.replace(
+                "</head>",
+                format!("<link rel=\"manifest\" href=\"{base_path}{PWA_MANIFEST_ROUTE}\"></head>"),
+            );
+        }
+
+        if let Some(sw_path) = &self.service_worker {
+            let to_json = |value: &str| serde_json::to_string(value).unwrap_or_default();
+            let options = match &self.service_worker_scope {
+                Some(scope) => format!(", {{ scope: {} }}", to_json(scope)),
+                None => String::new(),
+            };
+
+            replacements.replace(
+                "</body>",
+                format!(
+                    "<script nonce=\"%CSP_NONCE%\">if (\"serviceWorker\" in navigator) {{ \
+                    navigator.serviceWorker.register({}{options}); }}</script></body>",
+                    to_json(sw_path)
+                ),
+            );
+        }
+
+        replacements.replace("%STATIC_PREFIX%", self.asset_prefix());
+
+        // user defined template variables, replaced after the built-in placeholders in the order they were set
+        for (key, value) in &self.template_vars {
+            replacements.replace(format!("%{key}%"), value);
+        }
+
+        // the nonce attributes of the built-in tags are only rendered when a nonce is generated
+        if !self.csp_nonce {
+            replacements.replace(" nonce=\"%CSP_NONCE%\"", "");
+        }
+    }
+
+    /// Error pages of the static router, proxying to esbuild a missing asset links to the files esbuild serves
+    fn error_pages(&self) -> HashMap<StatusCode, String> {
+        let mut error_pages = self.error_pages.clone();
+
+        // esbuild may still be building, or the asset is not imported by the entrypoint
+        if matches!(self.engine, SpaxumEngine::Proxy(..)) {
+            let scheme = if self.proxy_tls.is_some() {
+                "https"
+            } else {
+                "http"
+            };
+            let esbuild_url = format!("{scheme}://{}/", self.esbuild_upstream());
+            let hint = format!(
+                "<p>This asset might not be bundled yet, see the files served by esbuild at \
+                <a href=\"{esbuild_url}\">{esbuild_url}</a></p>"
+            );
+
+            let not_found = match error_pages.remove(&StatusCode::NOT_FOUND) {
+                Some(html) if html.contains("</body>") => {
+                    html.replace("</body>", &format!("{hint}</body>"))
+                }
+                Some(html) => format!("{html}{hint}"),
+                None => {
+                    format!("<!DOCTYPE html><html><body><h1>404 Not Found</h1>{hint}</body></html>")
+                }
+            };
+
+            error_pages.insert(StatusCode::NOT_FOUND, not_found);
+        }
+
+        error_pages
+    }
+
+    /// Add the linked favicon and the error pages to the router of the static assets
+    fn static_router(&self, static_router: Router) -> Router {
+        let static_router = match self.favicon.as_ref() {
+            Some(favicon @ &Favicon::Linked(_, _, extension)) => match favicon.route() {
+                Some(route) => static_router.route(&format!("/favicon.{extension}"), route),
+                None => static_router,
+            },
+            _ => static_router,
+        };
+
+        let error_pages = self.error_pages();
+
+        if error_pages.is_empty() {
+            static_router
+        } else {
+            static_router.layer(map_response_with_state(
+                Arc::new(error_pages),
+                error_page_handler,
+            ))
+        }
+    }
+
+    /// Serve the favicon from `/favicon.ico`, embedded or redirected to the static asset
+    fn favicon_routes(&self, mut router: Router<Arc<IndexState>>) -> Router<Arc<IndexState>> {
+        match self.favicon.as_ref() {
+            Some(favicon @ (Favicon::Bytes(..) | Favicon::Linked(..))) => {
+                if let Some(route) = favicon.route() {
+                    router = router.route(FAVICON_ROUTE, route);
+                }
+            }
+            Some(favicon @ (Favicon::Path(_) | Favicon::Bundled(_))) => {
+                // an icon is not necessarily an ICO file, browsers check the content
+                if let Some(location) = favicon.location(&self.static_prefix) {
+                    router = router.route(
+                        FAVICON_ROUTE,
+                        get(move || async move { Redirect::temporary(&location) }),
+                    );
+                }
+            }
+            None => {}
+        }
+
+        router
+    }
+
+    /// Response headers added to every response, with the HSTS, frame and content security policies
+    fn response_headers(&self, development: bool, csp: Option<&str>) -> HeaderMap {
+        let mut response_headers = self.response_headers.clone();
+        if let Some(hsts) = &self.hsts
+            && !development
+        {
+            response_headers.insert(STRICT_TRANSPORT_SECURITY, hsts.clone());
+        }
+
+        if let Some(frame_policy) = &self.frame_policy {
+            match HeaderValue::try_from(frame_policy.header_value()) {
+                Ok(value) => {
+                    response_headers.insert(X_FRAME_OPTIONS, value);
+                }
+                Err(_) => panic!("Invalid X-Frame-Options: {}", frame_policy.header_value()),
+            }
+        }
+
+        if let Some(csp) = csp {
+            match HeaderValue::try_from(render_policy(csp, None)) {
+                Ok(value) => {
+                    response_headers.insert(CONTENT_SECURITY_POLICY, value);
+                }
+                Err(_) => panic!("Invalid Content-Security-Policy: {csp}"),
+            }
+        }
+
+        response_headers
+    }
+
+    /// Get the axum router like `router`, the HTML template file of `set_html_template_path` is read
+    /// asynchronously and an error reading it is returned, instead of falling back to the built-in template
+    /// Esbuild is started without blocking the runtime, an error starting it is returned as well
+    pub async fn into_router<S>(mut self) -> Result<Router<S>, SpaxumError>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        if self.html_template.is_none()
+            && let Some(path) = &self.html_template_path
+        {
+            let template = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| SpaxumError::Template(path.clone(), e))?;
+
+            self.html_template = Some(template);
+        }
+
+        // a no-op for the engines without esbuild, `router` does not start esbuild again
+        self.start_proxy_async().await?;
+
+        Ok(self.router())
+    }
+
+    /// Get the axum router for the Spaxum instance, serves static assets (from the "/static" path by default)
+    pub fn router<S>(mut self) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let template = match (&self.html_template, &self.html_template_path) {
+            (Some(html), _) => html.clone(),
+            (None, Some(path)) => template_or_default(path, std::fs::read_to_string(path)),
+            (None, None) => INDEX_HTML.to_string(),
+        };
+
+        // assets served from memory do not change, neither does the template
+        let template_path = match self.engine {
+            SpaxumEngine::MemoryServe(..) => None,
+            _ => self.html_template_path.clone(),
+        };
+
+        // the esbuild public path of the release build is fixed at build time
+        if let Some(bundle_static_prefix) = &self.bundle_static_prefix
+            && *bundle_static_prefix != self.static_prefix
+        {
+            eprintln!(
+                "spaxum: the assets are bundled for static path {bundle_static_prefix}, but served from {}, \
+                use set_static_path in the build script instead of set_static_prefix",
+                self.static_prefix
+            );
+        }
+
+        let development = !matches!(self.engine, SpaxumEngine::MemoryServe(..));
+
+        let csp = match (&self.csp, &self.frame_policy) {
+            (Some(csp), Some(frame_policy)) => Some(frame_policy.append_to_policy(csp)),
+            (csp, _) => csp.clone(),
+        };
+
+        let mut replacements = self.head_replacements(&template, None);
+        let head_replacements = replacements.0.len();
+
+        // an external dev server names the entry files itself
+        let (entry_files, chunks) = match &self.engine {
+            SpaxumEngine::MemoryServe(entry_files, _, assets) => {
+                // esbuild names the chunks shared by ES module entrypoints `chunk-<hash>.js`
+                let chunks = assets
+                    .iter()
+                    .map(|asset| asset.route.trim_start_matches('/'))
+                    .filter(|route| route.starts_with("chunk-") && route.ends_with(".js"))
+                    .map(|route| route.to_string())
+                    .collect::<Vec<String>>();
+
+                (Some(entry_files.clone()), chunks)
+            }
+            SpaxumEngine::Proxy(..) | SpaxumEngine::WatchDir(..) => {
+                (Some(self.dev_entry_files()), Vec::new())
+            }
+            SpaxumEngine::External(..) => (None, Vec::new()),
+        };
+
+        let preload_links = entry_files
+            .as_ref()
+            .filter(|_| self.preload_hints)
+            .map(|entry_files| entry_files.preload_links(&self.asset_prefix()));
+
+        let engine_routes = match &mut self.engine {
+            SpaxumEngine::MemoryServe(entry_files, memory_serve, assets) => {
+                // the memory serve instance is moved into its router, the engine is not used after routing
+                let memory_serve = std::mem::replace(memory_serve, MemoryServe::new(&[]));
+                let (entry_files, assets) = (entry_files.clone(), *assets);
+
+                self.memory_serve_routes(&entry_files, memory_serve, assets, &mut replacements)
+            }
+            SpaxumEngine::Proxy(_, dist_dir) => {
+                let dist_dir = PathBuf::from(dist_dir.as_str());
+
+                self.proxy_routes(dist_dir, &mut replacements)
+            }
+            SpaxumEngine::WatchDir(_, dist_dir) => {
+                let dist_dir = Arc::new(dist_dir.clone());
+
+                self.watch_dir_routes(dist_dir, &mut replacements)
+            }
+            SpaxumEngine::External(authority, base_path) => {
+                let (authority, base_path) = (authority.clone(), base_path.clone());
+
+                self.external_routes(authority, base_path, &mut replacements)
+            }
+        };
+
+        if let Some(entry_files) = entry_files.filter(|_| self.preload_entries) {
+            self.preload_replacements(&mut replacements, &template, &entry_files, &chunks);
+        }
+
+        let pwa_manifest = self
+            .pwa_manifest
+            .as_ref()
+            .map(|manifest| manifest.to_json(&self.static_prefix));
+
+        self.page_replacements(&mut replacements, pwa_manifest.is_some());
+
+        let response_headers = self.response_headers(development, csp.as_deref());
+
+        // the head replacements depend on the template, the replacements after them are shared
+        let locales = self
+            .locale_templates
+            .iter()
+            .map(|(locale, template)| {
+                let mut locale_replacements = self.head_replacements(template, Some(locale));
+                locale_replacements
+                    .0
+                    .extend_from_slice(&replacements.0[head_replacements..]);
+
+                let locale_index = LocaleIndex {
+                    html: locale_replacements.render(template),
+                    rendered: OnceCell::new(),
+                };
+
+                (locale.clone(), locale_index)
+            })
+            .collect();
+
+        let static_router = self.static_router(engine_routes.static_router);
+
+        // the service worker is served from its own path, it only controls the pages below that path
+        let service_worker_router = self.service_worker.as_ref().map(|sw_path| {
+            let static_router = static_router.clone();
+
+            (
+                sw_path,
+                get(move |request: Request| service_worker_handler(static_router, request)),
+            )
+        });
+
+        let mut router = Router::new().nest_service(&self.static_prefix, static_router);
+
+        if let Some((sw_path, sw_handler)) = service_worker_router {
+            router = router.route(sw_path, sw_handler);
+        }
+
+        // routes take precedence over the fallback, so the health check is not served the index.html
+        if let Some(health_route) = &self.health_route {
+            router = router.route(health_route, engine_routes.health);
+        }
+
+        if let Some(asset_manifest_route) = &self.asset_manifest_route {
+            router = router.route(asset_manifest_route, engine_routes.asset_manifest);
+        }
+
+        router = self.favicon_routes(router);
+
+        if let Some(robots_txt) = self.robots_txt {
+            router =
+                router.route(
+                    "/robots.txt",
+                    get(move || async move {
+                        ([(CONTENT_TYPE, "text/plain; charset=utf-8")], robots_txt)
+                    }),
+                );
+        }
+
+        if let Some(pwa_manifest) = pwa_manifest {
+            router = router.route(
+                PWA_MANIFEST_ROUTE,
+                get(move || async move {
+                    ([(CONTENT_TYPE, "application/manifest+json")], pwa_manifest)
+                }),
+            );
+        }
+
+        let router = match preload_links.and_then(|links| HeaderValue::try_from(links).ok()) {
+            Some(links) => router.fallback(
+                index_handler.layer(map_response_with_state(links, preload_links_handler)),
+            ),
+            None => router.fallback(index_handler),
+        };
+
+        let index = IndexState {
+            html: replacements.render(&template),
+            template_path,
+            replacements,
+            process_index: self.process_index,
+            process_index_per_request: self.process_index_per_request,
+            html_minification: self.html_minification,
+            csp_nonce: self.csp_nonce,
+            csp: csp.clone(),
+            rendered: OnceCell::new(),
+            locales,
+            inline_css: engine_routes.inline_css,
+        };
+
+        let router = router.with_state(Arc::new(index));
+
+        if response_headers.is_empty() {
+            router
+        } else {
+            router.layer(map_response_with_state(
+                Arc::new(response_headers),
+                response_headers_handler,
+            ))
+        }
+    }
+}
+
+/// Parse the JSON encoded esbuild defines passed by the build script, used by the `load!` macro
+pub fn parse_defines(defines: &str) -> Vec<String> {
+    serde_json::from_str(defines).unwrap_or_default()
+}
+
+/// Serve the service worker from the static router
+async fn service_worker_handler(mut static_router: Router, mut request: Request) -> Response {
+    *request.uri_mut() = format!("/{SERVICE_WORKER_FILE}")
+        .parse::<Uri>()
+        .expect("invalid service worker uri");
+
+    match static_router.call(request).await {
+        Ok(response) => response.into_response(),
+        Err(e) => match e {},
+    }
+}
+
+/// Serve the index.html, processed by the process html hook
+/// When CSP nonces are enabled a fresh nonce is substituted and sent in the Content-Security-Policy header
+async fn index_handler(State(index): State<Arc<IndexState>>, request: Request) -> Response {
+    let locale = request
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .filter(|_| !index.locales.is_empty())
+        .and_then(|accept_language| {
+            best_locale(accept_language, index.locales.keys().map(String::as_str))
+        })
+        .and_then(|locale| index.locales.get(locale));
+
+    let html = match (locale, &index.template_path) {
+        (Some(locale), _) => locale
+            .rendered
+            .get_or_init(|| index.process(locale.html.clone()))
+            .await
+            .clone(),
+        (None, Some(template_path)) => {
+            let template = template_or_default(
+                template_path,
+                tokio::fs::read_to_string(template_path).await,
+            );
+
+            index.process(index.replacements.render(&template)).await
+        }
+        (None, None) => index
+            .rendered
+            .get_or_init(|| index.process(index.html.clone()))
+            .await
+            .clone(),
+    };
+
+    // the nonce is substituted before the per request hook, so request data echoed by the hook is not given the nonce
+    let nonce = index.csp_nonce.then(generate_nonce);
+    let html = match &nonce {
+        Some(nonce) => html.replace(CSP_NONCE, nonce),
+        None => html,
+    };
+
+    let html = match &index.process_index_per_request {
+        Some(process_index) => {
+            let (mut parts, _) = request.into_parts();
+            if let Some(nonce) = &nonce {
+                parts.extensions.insert(CspNonce(nonce.clone()));
+            }
+
+            process_index(html, &parts)
+        }
+        None => html,
+    };
+
+    let mut response = match nonce {
+        Some(nonce) => {
+            let csp = match &index.csp {
+                Some(csp) => render_policy(csp, Some(&nonce)),
+                None => format!("script-src 'self' 'nonce-{nonce}'"),
+            };
+
+            ([(CONTENT_SECURITY_POLICY, csp)], Html(html)).into_response()
+        }
+        None => Html(html).into_response(),
+    };
+
+    // caches have to store the index.html per language
+    if !index.locales.is_empty() {
+        response
+            .headers_mut()
+            .insert(VARY, HeaderValue::from_static("accept-language"));
+    }
+
+    response
+}
+
+/// Replace the body of error responses by the configured error page
+/// Source maps are requested by the browser devtools, which expect a plain error instead of a page
+async fn error_page_handler(
+    State(error_pages): State<Arc<HashMap<StatusCode, String>>>,
+    uri: Uri,
+    response: Response,
+) -> Response {
+    if uri.path().ends_with(".map") {
+        return response;
+    }
+
+    match error_pages.get(&response.status()) {
+        Some(html) => (response.status(), Html(html.clone())).into_response(),
+        None => response,
+    }
+}
+
+/// Add the `Link` header to preload the entry files to successful index.html responses
+async fn preload_links_handler(
+    State(links): State<HeaderValue>,
+    mut response: Response,
+) -> Response {
+    if response.status().is_success() {
+        response.headers_mut().append(LINK, links);
+    }
+
+    response
+}
+
+/// Add the configured headers to every response, headers set by a handler take precedence
+async fn response_headers_handler(
+    State(headers): State<Arc<HeaderMap>>,
+    mut response: Response,
+) -> Response {
+    for (name, value) in headers.iter() {
+        if !response.headers().contains_key(name) {
+            response.headers_mut().insert(name, value.clone());
+        }
+    }
+
+    response
+}
+
+/// Health check, the application is serving requests
+async fn health_handler() -> &'static str {
+    "ok"
+}
+
+/// Generate a random base64 encoded nonce for the Content-Security-Policy
+fn generate_nonce() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).expect("unable to generate a random nonce");
+
+    STANDARD.encode(bytes)
+}
+
+/// The `<html>` start tag of the template, and the tag with its `lang` attribute set to the language
+fn html_lang_replacement(template: &str, lang: &str) -> Option<(String, String)> {
+    let start = template.find("<html")?;
+    let end = start + template[start..].find('>')? + 1;
+    let html_tag = &template[start..end];
+    let lang = escape_html(lang);
+
+    let with_lang = match html_tag.find(" lang=\"") {
+        Some(attribute) => {
+            let value = attribute + " lang=\"".len();
+            let value_end = value + html_tag[value..].find('"')?;
+
+            format!("{}{lang}{}", &html_tag[..value], &html_tag[value_end..])
+        }
+        None => html_tag.replacen("<html", &format!("<html lang=\"{lang}\""), 1),
+    };
+
+    Some((html_tag.to_string(), with_lang))
+}
+
+/// Normalize a base href to a path with a leading and trailing slash, `None` for the root
+pub(crate) fn normalize_base_href(base_href: &str) -> Option<String> {
+    let path = base_href.trim().trim_matches('/');
+
+    (!path.is_empty()).then(|| format!("/{path}/"))
+}
+
+/// URLs of the preload link tags in the HTML
+fn preloaded_hrefs(html: &str) -> Vec<&str> {
+    html.split("<link")
+        .skip(1)
+        .filter_map(|tag| {
+            let tag = &tag[..tag.find('>')?];
+            if !tag.contains("preload") {
+                return None;
+            }
+
+            tag.split("href=\"").nth(1)?.split('"').next()
+        })
+        .collect()
+}
+
+/// Use the HTML template read from a file, or the built-in template if the file could not be read
+fn template_or_default(path: &Path, template: std::io::Result<String>) -> String {
+    match template {
+        Ok(template) => template,
+        Err(e) => {
+            eprintln!(
+                "spaxum: unable to read HTML template {}, using the built-in template: {e}",
+                path.to_string_lossy()
+            );
+
+            INDEX_HTML.to_string()
+        }
+    }
+}
+
+/// Error creating the router with `Spaxum::into_router`
+#[derive(Debug)]
+pub enum SpaxumError {
+    /// The HTML template file could not be read
+    Template(PathBuf, std::io::Error),
+    /// Esbuild was not found, is too old, failed to start or failed to bundle
+    Esbuild(String),
+}
+
+impl std::fmt::Display for SpaxumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpaxumError::Template(path, e) => {
+                write!(
+                    f,
+                    "unable to read HTML template {}: {e}",
+                    path.to_string_lossy()
+                )
+            }
+            SpaxumError::Esbuild(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for SpaxumError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SpaxumError::Template(_, e) => Some(e),
+            SpaxumError::Esbuild(_) => None,
+        }
+    }
+}
+
+/// Oldest supported esbuild version, the watch and serve flags changed in 0.17
+const ESBUILD_MIN_VERSION: [u32; 3] = [0, 17, 0];
+
+/// Esbuild executable and the arguments to run it with, e.g. through npx
+#[derive(Debug, Clone)]
+struct Esbuild {
+    program: PathBuf,
+    args: Vec<String>,
+    /// Where the executable was found
+    source: &'static str,
+}
+
+impl Esbuild {
+    /// Run the esbuild executable directly
+    fn new(program: impl Into<PathBuf>, source: &'static str) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            source,
+        }
+    }
+
+    /// Create a command that runs esbuild, the esbuild arguments are added by the caller
+    fn command(&self) -> std::process::Command {
+        let mut command = std::process::Command::new(&self.program);
+        command.args(&self.args);
+
+        command
+    }
+}
+
+impl std::fmt::Display for Esbuild {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program.to_string_lossy())?;
+
+        for arg in &self.args {
+            write!(f, " {arg}")?;
+        }
+
+        write!(f, " ({})", self.source)
+    }
+}
+
+/// Resolve the esbuild executable and check that it runs and is recent enough
+/// Returns the executable and the version, or an error explaining how to install esbuild
+fn probe_esbuild(entrypoint_dir: Option<&Path>) -> Result<(Esbuild, String), String> {
+    let esbuild = get_esbuild_path(entrypoint_dir)?;
+    let path = esbuild.to_string();
+
+    let output = match esbuild.command().arg("--version").output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(format!(
+                "esbuild not found at {path}, install it with `npm i -g esbuild` \
+                or set SPAXUM_ESBUILD_PATH to the esbuild executable"
+            ));
+        }
+        Err(e) => return Err(format!("Unable to run esbuild at {path}: {e}")),
+    };
+
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let parsed = version
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse::<u32>())
+        .collect::<Result<Vec<u32>, _>>();
+
+    let Ok(parsed) = parsed else {
+        return Err(format!(
+            "Unable to determine the version of esbuild at {path}, got \"{version}\""
+        ));
+    };
+
+    if parsed.as_slice() < ESBUILD_MIN_VERSION.as_slice() {
+        let [major, minor, patch] = ESBUILD_MIN_VERSION;
+
+        return Err(format!(
+            "esbuild {version} at {path} is too old, spaxum requires esbuild {major}.{minor}.{patch} or newer"
+        ));
+    }
+
+    Ok((esbuild, version))
+}
+
+/// Get the esbuild executable
+/// The `SPAXUM_ESBUILD_PATH` environment variable takes precedence, it must point to an executable
+/// Otherwise the esbuild binary shipped with spaxum, the system esbuild, the esbuild of the
+/// node_modules directory of the frontend or npx is used, in that order
+fn get_esbuild_path(entrypoint_dir: Option<&Path>) -> Result<Esbuild, String> {
+    if let Some(esbuild) = std::env::var_os("SPAXUM_ESBUILD_PATH") {
+        let esbuild = PathBuf::from(esbuild);
+
+        let Ok(metadata) = esbuild.metadata() else {
+            return Err(format!(
+                "SPAXUM_ESBUILD_PATH {} does not exist",
+                esbuild.to_string_lossy()
+            ));
+        };
+
+        #[cfg(unix)]
+        let executable = {
+            use std::os::unix::fs::PermissionsExt;
+
+            metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+        };
+        #[cfg(not(unix))]
+        let executable = metadata.is_file();
+
+        if !executable {
+            return Err(format!(
+                "SPAXUM_ESBUILD_PATH {} is not an executable",
+                esbuild.to_string_lossy()
+            ));
+        }
+
+        return Ok(Esbuild::new(esbuild, "SPAXUM_ESBUILD_PATH"));
+    }
+
+    Ok(find_esbuild(entrypoint_dir))
+}
+
+/// Find the esbuild executable shipped with spaxum, the system esbuild or the esbuild node module
+fn find_esbuild(entrypoint_dir: Option<&Path>) -> Esbuild {
+    // the absolute path of the spaxum crate, `file!()` is relative to the workspace spaxum is compiled in
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    if let Some(esbuild) = bundled_esbuild(crate_dir, std::env::consts::OS, std::env::consts::ARCH)
+    {
+        return Esbuild::new(esbuild, "shipped with spaxum");
+    }
+
+    if let Some(esbuild) = find_in_path("esbuild") {
+        return Esbuild::new(esbuild, "PATH");
+    }
+
+    if let Some(esbuild) = entrypoint_dir.and_then(node_modules_esbuild) {
+        return Esbuild::new(esbuild, "node_modules");
+    }
+
+    if let Some(npx) = find_in_path("npx") {
+        return Esbuild {
+            program: npx,
+            args: vec!["--no-install".to_string(), "esbuild".to_string()],
+            source: "npx",
+        };
+    }
+
+    // not found, running it reports how to install esbuild
+    Esbuild::new("esbuild", "PATH")
+}
+
+/// Find an executable in the directories of the `PATH` environment variable
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let name = format!("{name}{}", std::env::consts::EXE_SUFFIX);
+
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(&name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Find `node_modules/.bin/esbuild` in the directory of the entrypoint or its parents
+/// The search stops at the outermost directory with a `package.json`, the root of the frontend project
+/// or its workspace, so workspaces with a hoisted node_modules directory are supported
+fn node_modules_esbuild(entrypoint_dir: &Path) -> Option<PathBuf> {
+    let name = if cfg!(windows) {
+        "esbuild.cmd"
+    } else {
+        "esbuild"
+    };
+
+    // the directories between a workspace package and the workspace root, e.g. `packages`, have no package.json
+    let root = entrypoint_dir
+        .ancestors()
+        .filter(|dir| dir.join("package.json").is_file())
+        .last()?;
+
+    entrypoint_dir
+        .ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .map(|dir| dir.join("node_modules").join(".bin").join(name))
+        .find(|esbuild| esbuild.is_file())
+}
+
+/// Find the esbuild executable for the platform in the given directory, named `esbuild-{os}-{arch}`
+/// The executable shipped as `esbuild` is for x86_64 Linux, only executables that match the platform are used
+fn bundled_esbuild(dir: &Path, os: &str, arch: &str) -> Option<PathBuf> {
+    let extension = if os == "windows" { ".exe" } else { "" };
+    let mut candidates = vec![dir.join(format!("esbuild-{os}-{arch}{extension}"))];
+
+    if os == "linux" && arch == "x86_64" {
+        candidates.push(dir.join("esbuild"));
+    }
+
+    candidates.into_iter().find(|candidate| {
+        let mut header = [0; 20];
+
+        std::fs::File::open(candidate)
+            .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut header))
+            .is_ok_and(|_| executable_matches_platform(&header, os, arch))
+    })
+}
+
+/// Check the header of an executable against the platform, ELF on Linux, Mach-O on macOS and PE on Windows
+fn executable_matches_platform(header: &[u8; 20], os: &str, arch: &str) -> bool {
+    match os {
+        // the machine of a little endian ELF executable is at offset 18
+        "linux" | "freebsd" | "netbsd" | "openbsd" => {
+            let machine = u16::from_le_bytes([header[18], header[19]]);
+
+            header.starts_with(b"\x7fELF")
+                && match arch {
+                    "x86_64" => machine == 62,
+                    "aarch64" => machine == 183,
+                    "x86" => machine == 3,
+                    "arm" => machine == 40,
+                    "riscv64" => machine == 243,
+                    _ => false,
+                }
+        }
+        // the cpu type of a 64 bit Mach-O executable follows the magic
+        "macos" => {
+            let cpu_type = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+            header.starts_with(&[0xcf, 0xfa, 0xed, 0xfe])
+                && match arch {
+                    "x86_64" => cpu_type == 0x0100_0007,
+                    "aarch64" => cpu_type == 0x0100_000c,
+                    _ => false,
+                }
+        }
+        "windows" => header.starts_with(b"MZ"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::{
+        body::{Body, to_bytes},
+        http::{
+            Method,
+            header::{ACCEPT, CONTENT_LENGTH, DATE, ETAG},
+        },
+    };
+
+    /// Frontend sources in a temporary directory, removed when dropped
+    pub(crate) struct TestFrontend {
+        dir: PathBuf,
+    }
+
+    impl TestFrontend {
+        /// Create a frontend with an `index.js` entrypoint
+        pub(crate) fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("spaxum-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).expect("unable to create the frontend directory");
+            std::fs::write(dir.join("index.js"), "console.log(\"spaxum\");\n")
+                .expect("unable to write the entrypoint");
+
+            Self { dir }
+        }
+
+        pub(crate) fn dir(&self) -> &Path {
+            &self.dir
+        }
+
+        pub(crate) fn entrypoint(&self) -> String {
+            self.dir.join("index.js").to_string_lossy().to_string()
+        }
+
+        pub(crate) fn dist_dir(&self) -> PathBuf {
+            self.dir.join("dist")
+        }
+    }
+
+    impl Drop for TestFrontend {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    /// Send a request to the router
+    pub(crate) async fn request(router: &mut Router, method: Method, uri: &str) -> Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .expect("invalid request");
+
+        match router.call(request).await {
+            Ok(response) => response,
+            Err(e) => match e {},
+        }
+    }
+
+    /// Poll the ready route until it responds with `200 OK`, false if it does not within the timeout
+    async fn wait_ready_route(router: &mut Router, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        while tokio::time::Instant::now() < deadline {
+            let response = request(router, Method::GET, "/static/__spaxum/ready").await;
+
+            if response.status() == StatusCode::OK {
+                return true;
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        false
+    }
+
+    #[tokio::test]
+    async fn ready_route_proxy() {
+        let frontend = TestFrontend::new("ready-proxy");
+        let spaxum = Spaxum::new_proxy("Test", &frontend.entrypoint(), &frontend.dist_dir())
+            .set_proxy_port(18_801)
+            .with_ready_route();
+        let shutdown = spaxum.shutdown_handle();
+        let mut router: Router = spaxum.router();
+
+        assert!(wait_ready_route(&mut router, Duration::from_secs(10)).await);
+
+        shutdown.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn ready_route_watch_dir() {
+        let frontend = TestFrontend::new("ready-watch-dir");
+        let spaxum = Spaxum::new_proxy("Test", &frontend.entrypoint(), &frontend.dist_dir())
+            .set_dev_serve_from_disk(true)
+            .with_ready_route();
+        let shutdown = spaxum.shutdown_handle();
+        let mut router: Router = spaxum.router();
+
+        assert!(wait_ready_route(&mut router, Duration::from_secs(10)).await);
+
+        let response = request(&mut router, Method::GET, "/static/index.js").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        shutdown.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn watch_dir_reloads_above_info_log_level() {
+        let frontend = TestFrontend::new("watch-dir-reload");
+        let spaxum = Spaxum::new_proxy("Test", &frontend.entrypoint(), &frontend.dist_dir())
+            .set_dev_serve_from_disk(true)
+            .set_esbuild_log_level(LogLevel::Warning)
+            .with_ready_route();
+        let shutdown = spaxum.shutdown_handle();
+        let mut router: Router = spaxum.router();
+
+        assert!(wait_ready_route(&mut router, Duration::from_secs(10)).await);
+
+        // wait for the first build to settle, esbuild does not log it at this level
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        while !frontend.dist_dir().join("index.js").exists() {
+            assert!(tokio::time::Instant::now() < deadline, "no first build");
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let response = request(&mut router, Method::GET, "/static/__spaxum/reload").await;
+        let mut body = response.into_body().into_data_stream();
+
+        std::fs::write(frontend.entrypoint(), "console.log(\"reloaded\");\n").unwrap();
+
+        let mut events = String::new();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        while !events.contains("event: reload") && tokio::time::Instant::now() < deadline {
+            if let Some(chunk) = crate::proxy::tests::next_chunk(&mut body).await {
+                events.push_str(&String::from_utf8_lossy(&chunk));
+            }
+        }
+
+        shutdown.shutdown().await;
+        assert!(events.contains("event: reload"), "no reload in {events:?}");
+    }
+
+    #[tokio::test]
+    async fn ready_route_bundle_once() {
+        let frontend = TestFrontend::new("ready-bundle-once");
+        let mut router: Router =
+            Spaxum::new_proxy("Test", &frontend.entrypoint(), &frontend.dist_dir())
+                .set_dev_watch(false)
+                .with_ready_route()
+                .router();
+
+        let response = request(&mut router, Method::GET, "/static/__spaxum/ready").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn failed_bundle_once_does_not_panic() {
+        let frontend = TestFrontend::new("failed-bundle-once");
+        std::fs::write(frontend.dir().join("index.js"), "console.log(\"spaxum\"").unwrap();
+
+        let mut router: Router =
+            Spaxum::new_proxy("Test", &frontend.entrypoint(), &frontend.dist_dir())
+                .set_dev_watch(false)
+                .with_ready_route()
+                .router();
+
+        let response = request(&mut router, Method::GET, "/static/__spaxum/ready").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("esbuild failed to bundle"));
+
+        let result = Spaxum::new_proxy("Test", &frontend.entrypoint(), &frontend.dist_dir())
+            .set_dev_watch(false)
+            .into_router::<()>()
+            .await;
+        assert!(matches!(result, Err(SpaxumError::Esbuild(_))));
+    }
+
+    /// Executable header of the platform, padded to the 20 bytes that are checked
+    fn header(magic: &[u8], at: usize, machine: &[u8]) -> [u8; 20] {
+        let mut header = [0; 20];
+        header[..magic.len()].copy_from_slice(magic);
+        header[at..at + machine.len()].copy_from_slice(machine);
+
+        header
+    }
+
+    #[test]
+    fn executable_matches_elf() {
+        let x86_64 = header(b"\x7fELF", 18, &62u16.to_le_bytes());
+        let aarch64 = header(b"\x7fELF", 18, &183u16.to_le_bytes());
+
+        assert!(executable_matches_platform(&x86_64, "linux", "x86_64"));
+        assert!(!executable_matches_platform(&x86_64, "linux", "aarch64"));
+        assert!(executable_matches_platform(&aarch64, "linux", "aarch64"));
+        assert!(executable_matches_platform(&aarch64, "freebsd", "aarch64"));
+        assert!(!executable_matches_platform(&x86_64, "macos", "x86_64"));
+        assert!(!executable_matches_platform(&x86_64, "linux", "powerpc"));
+    }
+
+    #[test]
+    fn executable_matches_mach_o() {
+        let magic = [0xcf, 0xfa, 0xed, 0xfe];
+        let x86_64 = header(&magic, 4, &0x0100_0007u32.to_le_bytes());
+        let aarch64 = header(&magic, 4, &0x0100_000cu32.to_le_bytes());
+
+        assert!(executable_matches_platform(&x86_64, "macos", "x86_64"));
+        assert!(executable_matches_platform(&aarch64, "macos", "aarch64"));
+        assert!(!executable_matches_platform(&aarch64, "macos", "x86_64"));
+        assert!(!executable_matches_platform(&aarch64, "linux", "aarch64"));
+    }
+
+    #[test]
+    fn executable_matches_pe() {
+        let pe = header(b"MZ", 2, &[]);
+
+        assert!(executable_matches_platform(&pe, "windows", "x86_64"));
+        assert!(!executable_matches_platform(&pe, "linux", "x86_64"));
+        assert!(!executable_matches_platform(&pe, "haiku", "x86_64"));
+    }
+
+    #[test]
+    fn bundled_esbuild_for_platform() {
+        let frontend = TestFrontend::new("bundled-esbuild");
+        let dir = frontend.dir();
+        let elf_x86_64 = header(b"\x7fELF", 18, &62u16.to_le_bytes());
+        let mach_o_aarch64 = header(&[0xcf, 0xfa, 0xed, 0xfe], 4, &0x0100_000cu32.to_le_bytes());
+
+        std::fs::write(dir.join("esbuild"), elf_x86_64).unwrap();
+        std::fs::write(dir.join("esbuild-macos-aarch64"), mach_o_aarch64).unwrap();
+        std::fs::write(
+            dir.join("esbuild-windows-x86_64.exe"),
+            header(b"MZ", 2, &[]),
+        )
+        .unwrap();
+        // shipped for the wrong platform, e.g. an x86_64 binary named for arm
+        std::fs::write(dir.join("esbuild-linux-aarch64"), elf_x86_64).unwrap();
+
+        assert_eq!(
+            bundled_esbuild(dir, "linux", "x86_64"),
+            Some(dir.join("esbuild"))
+        );
+        assert_eq!(
+            bundled_esbuild(dir, "macos", "aarch64"),
+            Some(dir.join("esbuild-macos-aarch64"))
+        );
+        assert_eq!(
+            bundled_esbuild(dir, "windows", "x86_64"),
+            Some(dir.join("esbuild-windows-x86_64.exe"))
+        );
+        assert_eq!(bundled_esbuild(dir, "linux", "aarch64"), None);
+        assert_eq!(bundled_esbuild(dir, "macos", "x86_64"), None);
+    }
+
+    #[test]
+    fn bundled_esbuild_truncated() {
+        let frontend = TestFrontend::new("bundled-esbuild-truncated");
+        std::fs::write(frontend.dir().join("esbuild"), b"\x7fELF").unwrap();
+
+        assert_eq!(bundled_esbuild(frontend.dir(), "linux", "x86_64"), None);
+    }
+
+    #[tokio::test]
+    async fn proxy_head_request() {
+        let upstream = proxy::tests::mock_upstream(Router::new().route(
+            "/index.js",
+            get(|| async {
+                (
+                    [(CONTENT_TYPE, "text/javascript"), (ETAG, "\"index\"")],
+                    "console.log(\"spaxum\");\n",
+                )
+            }),
+        ))
+        .await;
+        let mut router: Router =
+            Spaxum::new_external_proxy("Test", &format!("http://{upstream}")).router();
+
+        let get_response = request(&mut router, Method::GET, "/static/index.js").await;
+        let head_response = request(&mut router, Method::HEAD, "/static/index.js").await;
+
+        assert_eq!(get_response.status(), StatusCode::OK);
+        assert_eq!(head_response.status(), StatusCode::OK);
+
+        let mut get_headers = get_response.headers().clone();
+        let mut head_headers = head_response.headers().clone();
+        get_headers.remove(DATE);
+        head_headers.remove(DATE);
+        assert_eq!(head_headers, get_headers);
+        assert_eq!(head_headers[CONTENT_LENGTH], "23");
+
+        let body = to_bytes(head_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn proxy_esbuild_event_stream() {
+        let frontend = TestFrontend::new("esbuild-events");
+        let spaxum = Spaxum::new_proxy("Test", &frontend.entrypoint(), &frontend.dist_dir())
+            .set_proxy_port(18_802)
+            .with_ready_route();
+        let shutdown = spaxum.shutdown_handle();
+        let mut router: Router = spaxum.router();
+
+        assert!(wait_ready_route(&mut router, Duration::from_secs(10)).await);
+
+        // esbuild only serves the events to an event source
+        let event_source = Request::get("/static/esbuild")
+            .header(ACCEPT, "text/event-stream")
+            .body(Body::empty())
+            .unwrap();
+        let response = match router.call(event_source).await {
+            Ok(response) => response,
+            Err(e) => match e {},
+        };
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/event-stream");
+        let mut body = response.into_body().into_data_stream();
+
+        std::fs::write(frontend.entrypoint(), "console.log(\"changed\");\n").unwrap();
+
+        // esbuild notices the change and sends a change event, while the stream stays open
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        let mut events = String::new();
+        while !events.contains("event: change") && tokio::time::Instant::now() < deadline {
+            if let Some(chunk) = proxy::tests::next_chunk(&mut body).await {
+                events.push_str(&String::from_utf8_lossy(&chunk));
+            }
+        }
+        assert!(
+            events.contains("event: change"),
+            "no change event: {events}"
+        );
+
+        shutdown.shutdown().await;
+    }
+
+    /// Esbuild arguments of the engine, like `start_proxy` passes them
+    fn dev_args(spaxum: &Spaxum) -> Vec<String> {
+        match &spaxum.engine {
+            SpaxumEngine::Proxy(entrypoints, dist_dir) => {
+                spaxum.esbuild_dev_args(entrypoints, dist_dir, true)
+            }
+            SpaxumEngine::WatchDir(entrypoints, dist_dir) => {
+                spaxum.esbuild_dev_args(entrypoints, &dist_dir.to_string_lossy(), false)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn dev_esbuild_args_only_while_watching() {
+        let frontend = TestFrontend::new("dev-esbuild-args");
+        let spaxum = || {
+            Spaxum::new_proxy("Test", &frontend.entrypoint(), &frontend.dist_dir())
+                .set_esbuild_args(vec!["--jsx=automatic".to_string()])
+                .set_dev_esbuild_args(vec!["--define:__DEV__=true".to_string()])
+        };
+
+        let watch = dev_args(&spaxum());
+        assert!(watch.contains(&"--watch=forever".to_string()));
+        assert!(watch.contains(&"--jsx=automatic".to_string()));
+        assert!(watch.contains(&"--define:__DEV__=true".to_string()));
+
+        let watch_dir = dev_args(&spaxum().set_dev_serve_from_disk(true));
+        assert!(watch_dir.contains(&"--define:__DEV__=true".to_string()));
+        assert!(!watch_dir.iter().any(|arg| arg.starts_with("--serve=")));
+
+        // bundling once builds like the release build, without the dev-only arguments
+        let once = dev_args(&spaxum().set_dev_watch(false));
+        assert!(!once.contains(&"--watch=forever".to_string()));
+        assert!(once.contains(&"--jsx=automatic".to_string()));
+        assert!(!once.contains(&"--define:__DEV__=true".to_string()));
+    }
+
+    /// Create an empty file, and its parent directories
+    fn touch(path: &Path) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn node_modules_esbuild_of_project() {
+        let frontend = TestFrontend::new("node-modules-project");
+        let project = frontend.dir().join("frontend");
+        touch(&project.join("package.json"));
+        touch(&project.join("node_modules/.bin/esbuild"));
+        std::fs::create_dir_all(project.join("src")).unwrap();
+
+        assert_eq!(
+            node_modules_esbuild(&project.join("src")),
+            Some(project.join("node_modules/.bin/esbuild"))
+        );
+    }
+
+    #[test]
+    fn node_modules_esbuild_hoisted() {
+        let frontend = TestFrontend::new("node-modules-hoisted");
+        let workspace = frontend.dir().join("workspace");
+        let app = workspace.join("packages/app");
+        touch(&workspace.join("package.json"));
+        touch(&workspace.join("node_modules/.bin/esbuild"));
+        touch(&app.join("package.json"));
+        std::fs::create_dir_all(app.join("src")).unwrap();
+
+        assert_eq!(
+            node_modules_esbuild(&app.join("src")),
+            Some(workspace.join("node_modules/.bin/esbuild"))
+        );
+    }
+
+    #[test]
+    fn node_modules_esbuild_outside_project() {
+        let frontend = TestFrontend::new("node-modules-outside");
+        let app = frontend.dir().join("app");
+        // not part of the frontend project, the directory has no package.json
+        touch(&frontend.dir().join("node_modules/.bin/esbuild"));
+        touch(&app.join("package.json"));
+
+        assert_eq!(node_modules_esbuild(&app), None);
+    }
+
+    #[test]
+    fn bundled_esbuild_as_registry_dependency() {
+        // a registry dependency is compiled from the cargo home, not from the workspace
+        let frontend = TestFrontend::new("registry-dependency");
+        let crate_dir = frontend
+            .dir()
+            .join("registry/src/index.crates.io-1949cf8c6b5b557f/spaxum-0.3.0");
+        std::fs::create_dir_all(&crate_dir).unwrap();
+        std::fs::write(
+            crate_dir.join("esbuild-linux-aarch64"),
+            header(b"\x7fELF", 18, &183u16.to_le_bytes()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            bundled_esbuild(&crate_dir, "linux", "aarch64"),
+            Some(crate_dir.join("esbuild-linux-aarch64"))
+        );
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn shipped_esbuild_as_path_dependency() {
+        // the path of the crate is absolute, so it does not depend on the working directory
+        let esbuild = find_esbuild(None);
+
+        assert_eq!(esbuild.source, "shipped with spaxum");
+        assert!(esbuild.program.is_absolute());
+        assert_eq!(
+            esbuild.program,
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("esbuild")
+        );
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn shipped_esbuild_from_other_working_directory() {
+        let frontend = TestFrontend::new("other-working-directory");
+
+        // the working directory is shared by the tests, so the lookup runs in a separate process
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "tests::shipped_esbuild_as_path_dependency"])
+            .current_dir(frontend.dir())
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("1 passed"));
+    }
+
+    #[tokio::test]
+    async fn two_apps_on_different_ports() {
+        let mut apps = Vec::new();
+
+        for (name, port) in [("first", 18_803), ("second", 18_804)] {
+            let frontend = TestFrontend::new(&format!("two-apps-{name}"));
+            std::fs::write(frontend.entrypoint(), format!("console.log(\"{name}\");\n")).unwrap();
+
+            let spaxum = Spaxum::new_proxy("Test", &frontend.entrypoint(), &frontend.dist_dir())
+                .set_proxy_port(port)
+                .with_ready_route();
+            let shutdown = spaxum.shutdown_handle();
+            let router: Router = spaxum.router();
+
+            apps.push((name, frontend, shutdown, router));
+        }
+
+        for (name, _, _, router) in &mut apps {
+            assert!(wait_ready_route(router, Duration::from_secs(10)).await);
+
+            // each proxy reaches its own esbuild dev server
+            let response = request(router, Method::GET, "/static/index.js").await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert!(String::from_utf8_lossy(&body).contains(&format!("\"{name}\"")));
+        }
+
+        for (_, _, shutdown, _) in apps {
+            shutdown.shutdown().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn template_vars_in_order() {
+        let mut router: Router = Spaxum::new_external_proxy("Test", "http://127.0.0.1:1")
+            .set_html_template("<html><body>%GREETING% %NAME%</body></html>")
+            .set_template_var("GREETING", "Hello %NAME%,")
+            .set_template_var("NAME", "world")
+            .set_template_var("GREETING", "Hi %NAME%,")
+            .router();
+
+        let response = request(&mut router, Method::GET, "/").await;
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        assert!(String::from_utf8_lossy(&body).contains("Hi world, world"));
+    }
+
+    /// Entry files of a release build without assets
+    pub(crate) fn entry_files() -> EntryFiles {
+        EntryFiles {
+            js: "index.js".to_string(),
+            css: "index.css".to_string(),
+            js_sri: String::new(),
+            css_sri: String::new(),
+            additional: Vec::new(),
+            module: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn per_request_hook_does_not_get_nonce_placeholders() {
+        let mut router: Router = Spaxum::new("Test", &[], entry_files())
+            .with_csp_nonce()
+            .set_process_html_per_request(|html, parts| {
+                let echo = parts.headers["x-echo"].to_str().unwrap_or_default();
+                let CspNonce(nonce) = parts.extensions.get::<CspNonce>().unwrap();
+
+                html.replace("</body>", &format!("<p>{echo}</p><i>{nonce}</i></body>"))
+            })
+            .router();
+
+        let request = Request::get("/")
+            .header("x-echo", "%CSP_NONCE%")
+            .body(Body::empty())
+            .unwrap();
+        let response = match router.call(request).await {
+            Ok(response) => response,
+            Err(e) => match e {},
+        };
+        let csp = response.headers()[CONTENT_SECURITY_POLICY]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let html = String::from_utf8_lossy(&body);
+
+        let nonce = html
+            .split("<i>")
+            .nth(1)
+            .unwrap()
+            .split("</i>")
+            .next()
+            .unwrap();
+        assert!(csp.contains(&format!("'nonce-{nonce}'")));
+        assert!(html.contains(&format!("nonce=\"{nonce}\"")));
+        assert!(html.contains("<p>%CSP_NONCE%</p>"));
+    }
+
+    #[tokio::test]
+    async fn locale_template_head_replacements() {
+        let mut router: Router = Spaxum::new("Test", &[], entry_files())
+            .add_meta("description", "Spaxum")
+            .add_locale(
+                "fr",
+                "<html lang=\"%LANG%\"><head><title>%TITLE%</title></head><body></body></html>",
+            )
+            .router();
+
+        let request = Request::get("/")
+            .header(ACCEPT_LANGUAGE, "fr")
+            .body(Body::empty())
+            .unwrap();
+        let response = match router.call(request).await {
+            Ok(response) => response,
+            Err(e) => match e {},
+        };
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let html = String::from_utf8_lossy(&body);
+
+        assert!(html.contains("<html lang=\"fr\">"));
+        assert!(html.contains("<meta name=\"description\" content=\"Spaxum\"></head>"));
+    }
+
+    /// Count the ticks of a task while esbuild bundles once, the ticks stop if the runtime is blocked
+    fn ticker() -> Arc<std::sync::atomic::AtomicU32> {
+        let ticks = Arc::new(std::sync::atomic::AtomicU32::new(0));
+
+        tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                    ticks.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+        });
+
+        ticks
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn bundle_once_hands_off_the_worker() {
+        let frontend = TestFrontend::new("bundle-once-worker");
+        let ticks = ticker();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // on the only worker thread, like a handler that builds the router
+        let (entrypoint, dist_dir) = (frontend.entrypoint(), frontend.dist_dir());
+        let ticks_during_build = tokio::spawn(async move {
+            let before = ticks.load(std::sync::atomic::Ordering::Relaxed);
+            let _: Router = Spaxum::new_proxy("Test", &entrypoint, &dist_dir)
+                .set_dev_watch(false)
+                .router();
+
+            ticks.load(std::sync::atomic::Ordering::Relaxed) - before
+        })
+        .await
+        .unwrap();
+
+        assert!(frontend.dist_dir().join("index.js").exists());
+        assert!(ticks_during_build > 0);
+    }
+
+    #[tokio::test]
+    async fn into_router_bundles_once_without_blocking() {
+        let frontend = TestFrontend::new("bundle-once-async");
+        let ticks = ticker();
+
+        let _: Router = Spaxum::new_proxy("Test", &frontend.entrypoint(), &frontend.dist_dir())
+            .set_dev_watch(false)
+            .into_router()
+            .await
+            .unwrap();
+
+        assert!(frontend.dist_dir().join("index.js").exists());
+        assert!(ticks.load(std::sync::atomic::Ordering::Relaxed) > 0);
+    }
+
+    #[tokio::test]
+    async fn embedded_favicons_are_cached() {
+        let mut router: Router = Spaxum::new("Test", &[], entry_files())
+            .with_favicon(b"icon", "image/x-icon")
+            .router();
+
+        let response = request(&mut router, Method::GET, "/favicon.ico").await;
+        assert_eq!(response.headers()[CONTENT_TYPE], "image/x-icon");
+        assert_eq!(
+            response.headers()[CACHE_CONTROL],
+            FAVICON_BYTES_CACHE_CONTROL
+        );
+
+        let mut router: Router = Spaxum::new("Test", &[], entry_files())
+            .set_favicon(b"<svg></svg>", "image/svg+xml")
+            .router();
+
+        for uri in ["/favicon.ico", "/static/favicon.svg"] {
+            let response = request(&mut router, Method::GET, uri).await;
+            assert_eq!(response.headers()[CONTENT_TYPE], "image/svg+xml");
+            assert_eq!(response.headers()[CACHE_CONTROL], FAVICON_CACHE_CONTROL);
+        }
+    }
 }
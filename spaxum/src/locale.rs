@@ -0,0 +1,43 @@
+/// Pick the locale that best matches an `Accept-Language` header, e.g. `fr-CH, fr;q=0.9, en;q=0.8`
+/// Languages are tried by quality, a language matches a locale exactly, by its primary language
+/// (`fr-CH` matches `fr`), or a locale of the same primary language (`fr` matches `fr-BE`)
+/// Locales are lowercase, `None` if no locale matches
+pub(crate) fn best_locale<'a>(
+    accept_language: &str,
+    locales: impl Iterator<Item = &'a str> + Clone,
+) -> Option<&'a str> {
+    let mut languages = accept_language
+        .split(',')
+        .filter_map(|language| {
+            let mut parts = language.split(';');
+            let tag = parts.next()?.trim().to_ascii_lowercase();
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.trim().parse::<f32>().ok())?;
+
+            (!tag.is_empty() && tag != "*" && quality > 0.0).then_some((tag, quality))
+        })
+        .collect::<Vec<(String, f32)>>();
+
+    // a stable sort keeps the order of the header for equal qualities
+    languages.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    languages.iter().find_map(|(tag, _)| {
+        let primary = primary_language(tag);
+
+        locales
+            .clone()
+            .find(|locale| locale == tag)
+            .or_else(|| locales.clone().find(|locale| *locale == primary))
+            .or_else(|| {
+                locales
+                    .clone()
+                    .find(|locale| primary_language(locale) == primary)
+            })
+    })
+}
+
+/// Primary language of a language tag, e.g. `en` of `en-US`
+fn primary_language(tag: &str) -> &str {
+    tag.split(['-', '_']).next().unwrap_or(tag)
+}
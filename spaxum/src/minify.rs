@@ -0,0 +1,286 @@
+/// Minification pass for the index.html
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum HtmlMinification {
+    /// In-crate minifier, see [`minify_html`]
+    Simple,
+    /// Minifier of the `minify-html` crate
+    #[cfg(feature = "minify-html")]
+    Aggressive,
+}
+
+impl HtmlMinification {
+    /// Minify the HTML
+    pub(crate) fn minify(self, html: &str) -> String {
+        match self {
+            HtmlMinification::Simple => minify_html(html),
+            #[cfg(feature = "minify-html")]
+            HtmlMinification::Aggressive => minify_html_aggressive(html),
+        }
+    }
+}
+
+/// Elements of which the contents are copied as is
+const RAW_ELEMENTS: &[&str] = &["script", "style", "pre", "textarea"];
+
+/// Elements that are not rendered inline, whitespace next to their tags is not rendered either
+const BLOCK_ELEMENTS: &[&str] = &[
+    "!doctype",
+    "html",
+    "head",
+    "title",
+    "base",
+    "meta",
+    "link",
+    "script",
+    "style",
+    "noscript",
+    "template",
+    "body",
+    "main",
+    "header",
+    "footer",
+    "nav",
+    "aside",
+    "section",
+    "article",
+    "div",
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "ul",
+    "ol",
+    "li",
+    "dl",
+    "dt",
+    "dd",
+    "table",
+    "thead",
+    "tbody",
+    "tfoot",
+    "tr",
+    "th",
+    "td",
+    "form",
+    "fieldset",
+    "figure",
+    "figcaption",
+    "blockquote",
+    "details",
+    "summary",
+];
+
+/// Minify the HTML, collapses whitespace between tags, removes comments and
+/// strips leading and trailing whitespace from attribute values
+/// Whitespace between tags is collapsed to a space, and removed next to block elements
+/// The contents of `script`, `style`, `pre` and `textarea` elements are not changed
+fn minify_html(html: &str) -> String {
+    let mut minified = String::with_capacity(html.len());
+    let mut rest = html;
+    // the start of the document is not rendered inline
+    let mut previous_tag = "html";
+
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = match comment.find("-->") {
+                Some(end) => &comment[end + 3..],
+                None => "",
+            };
+        } else if rest.starts_with('<') {
+            let (tag, after_tag) = split_tag(rest);
+            minified.push_str(&minify_tag(tag));
+            previous_tag = tag_name(tag);
+            rest = after_tag;
+
+            // copy the contents of raw elements up to the closing tag
+            if let Some(name) = raw_element(tag) {
+                let end = find_closing_tag(rest, name).unwrap_or(rest.len());
+                minified.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..end];
+            rest = &rest[end..];
+
+            let next_tag = if rest.is_empty() {
+                "html"
+            } else {
+                tag_name(rest)
+            };
+            let between_blocks = is_block_element(previous_tag) || is_block_element(next_tag);
+
+            if !(text.trim().is_empty() && between_blocks) {
+                minified.push_str(&minify_text(text));
+            }
+        }
+    }
+
+    minified.trim().to_string()
+}
+
+/// Split the tag at the start of the HTML from the rest, quoted attribute values may contain `>`
+fn split_tag(html: &str) -> (&str, &str) {
+    let mut quote = None;
+
+    for (index, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return html.split_at(index + 1),
+            _ => {}
+        }
+    }
+
+    (html, "")
+}
+
+/// Collapse the whitespace in a tag and trim the attribute values
+fn minify_tag(tag: &str) -> String {
+    let mut minified = String::with_capacity(tag.len());
+    let mut chars = tag.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                let mut value = String::new();
+                for v in chars.by_ref() {
+                    if v == c {
+                        break;
+                    }
+                    value.push(v);
+                }
+
+                minified.push(c);
+                minified.push_str(value.trim());
+                minified.push(c);
+            }
+            c if c.is_whitespace() => {
+                while chars.next_if(|next| next.is_whitespace()).is_some() {}
+
+                // whitespace is only needed to separate attributes
+                if !matches!(chars.peek(), Some('>' | '/' | '=') | None) && !minified.ends_with('=')
+                {
+                    minified.push(' ');
+                }
+            }
+            c => minified.push(c),
+        }
+    }
+
+    minified
+}
+
+/// Collapse the whitespace in text to single spaces
+fn minify_text(text: &str) -> String {
+    let mut minified = String::with_capacity(text.len());
+    let mut whitespace = false;
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            whitespace = true;
+        } else {
+            if whitespace {
+                minified.push(' ');
+                whitespace = false;
+            }
+            minified.push(c);
+        }
+    }
+
+    if whitespace {
+        minified.push(' ');
+    }
+
+    minified
+}
+
+/// Element name of an opening or closing tag at the start of the HTML, e.g. `div` of `</div>`
+fn tag_name(tag: &str) -> &str {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()
+        .unwrap_or_default()
+}
+
+/// Check if whitespace next to the element is not rendered
+fn is_block_element(name: &str) -> bool {
+    BLOCK_ELEMENTS
+        .iter()
+        .any(|block| block.eq_ignore_ascii_case(name))
+}
+
+/// Name of the raw element opened by the tag, if any
+fn raw_element(tag: &str) -> Option<&'static str> {
+    // a closing tag does not open an element
+    if tag.starts_with("</") {
+        return None;
+    }
+
+    let name = tag_name(tag);
+
+    RAW_ELEMENTS
+        .iter()
+        .find(|raw| raw.eq_ignore_ascii_case(name))
+        .copied()
+}
+
+/// Find the closing tag of an element, case insensitive
+fn find_closing_tag(html: &str, name: &str) -> Option<usize> {
+    let closing = format!("</{name}");
+
+    html.to_ascii_lowercase().find(&closing)
+}
+
+/// Minify the HTML using the `minify-html` crate, also minifies inline CSS
+#[cfg(feature = "minify-html")]
+fn minify_html_aggressive(html: &str) -> String {
+    let mut cfg = minify_html::Cfg::new();
+    cfg.minify_css = true;
+    // keep the document structure intact for the process html hook
+    cfg.keep_closing_tags = true;
+    cfg.keep_html_and_head_opening_tags = true;
+    cfg.ensure_spec_compliant_unquoted_attribute_values = true;
+
+    String::from_utf8_lossy(&minify_html::minify(html.as_bytes(), &cfg)).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_whitespace_between_inline_siblings() {
+        assert_eq!(minify_html("<a>x</a>\n<a>y</a>"), "<a>x</a> <a>y</a>");
+        assert_eq!(
+            minify_html("<p><b>bold</b>\n    <i>italic</i></p>"),
+            "<p><b>bold</b> <i>italic</i></p>"
+        );
+    }
+
+    #[test]
+    fn removes_whitespace_between_block_elements() {
+        let html = "<!DOCTYPE html>\n<html>\n  <head>\n    <title>Test</title>\n  </head>\n  \
+            <body>\n    <div>a</div>\n    <div>b</div>\n  </body>\n</html>\n";
+
+        assert_eq!(
+            minify_html(html),
+            "<!DOCTYPE html><html><head><title>Test</title></head><body><div>a</div><div>b</div></body></html>"
+        );
+    }
+
+    #[test]
+    fn keeps_raw_elements() {
+        let html = "<pre>  a\n  b</pre>\n<script>let a  =  1;</script>";
+
+        assert_eq!(
+            minify_html(html),
+            "<pre>  a\n  b</pre><script>let a  =  1;</script>"
+        );
+    }
+}
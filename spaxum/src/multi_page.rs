@@ -0,0 +1,141 @@
+use axum::Router;
+use std::{net::IpAddr, sync::Arc};
+
+use crate::{Spaxum, SpaxumEngine, dev::DevState};
+
+/// Multiple pages served by one router, each page is a `Spaxum` instance with its own title and template
+/// Pages that serve the same assets, e.g. the entrypoints of one `bundle_multiple` build, share the static routes
+/// and the esbuild dev server, the shared assets are served once from their static prefix
+/// Chunks are only deduplicated within one build: esbuild splits the code the entrypoints have in common into
+/// shared chunks, there is no separate `/static/shared/` prefix for chunks of different builds
+/// The esbuild public path is fixed at build time, so pages with other assets need another static prefix,
+/// and a chunk they have in common with other pages is served from both prefixes
+#[derive(Default)]
+pub struct SpaxumMultiPage {
+    pages: Vec<(String, Spaxum)>,
+}
+
+/// Source of the assets of a page, pages with the same source share their static assets
+#[derive(Debug, PartialEq, Eq)]
+enum AssetSource {
+    /// Routes and ETags of the assets served from memory
+    Memory(Vec<(&'static str, &'static str)>),
+    /// Entrypoints and dist directory of esbuild
+    Esbuild(Vec<String>, String),
+    /// Address and base path of an external dev server
+    External(String, String),
+}
+
+/// Assets served by the first page with their source
+struct ServedAssets {
+    source: AssetSource,
+    static_prefix: String,
+    dev_state: Arc<DevState>,
+    proxy_host: IpAddr,
+    proxy_port: u16,
+}
+
+impl AssetSource {
+    /// Source of the assets of the page
+    fn of(spaxum: &Spaxum) -> Self {
+        match &spaxum.engine {
+            SpaxumEngine::MemoryServe(_, _, assets) => {
+                let mut assets = assets
+                    .iter()
+                    .map(|asset| (asset.route, asset.etag))
+                    .collect::<Vec<_>>();
+                assets.sort_unstable();
+
+                AssetSource::Memory(assets)
+            }
+            SpaxumEngine::Proxy(entrypoints, dist_dir) => {
+                AssetSource::Esbuild(entrypoints.clone(), dist_dir.clone())
+            }
+            SpaxumEngine::WatchDir(entrypoints, dist_dir) => {
+                AssetSource::Esbuild(entrypoints.clone(), dist_dir.to_string_lossy().to_string())
+            }
+            SpaxumEngine::External(authority, base_path) => {
+                AssetSource::External(authority.clone(), base_path.clone())
+            }
+        }
+    }
+}
+
+impl SpaxumMultiPage {
+    /// Create a router without pages
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a page served below the path prefix, e.g. `/admin`, the page with prefix `/` serves all other paths
+    pub fn add_page(mut self, path_prefix: &str, spaxum: Spaxum) -> Self {
+        let path_prefix = format!("/{}", path_prefix.trim_matches('/'));
+        self.pages.push((path_prefix, spaxum));
+
+        self
+    }
+
+    /// Create the router of all pages, each page router is nested under its prefix
+    /// Panics if pages with different assets use the same static prefix
+    pub fn router<S>(self) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let mut router = Router::new();
+        let mut served: Vec<ServedAssets> = Vec::new();
+
+        for (path_prefix, mut page) in self.pages {
+            let source = AssetSource::of(&page);
+
+            let serve_static = match served.iter().find(|served| served.source == source) {
+                Some(served) => {
+                    // the esbuild dev server of the first page serves the assets of this page as well
+                    page.static_prefix = served.static_prefix.clone();
+                    page.dev_state = served.dev_state.clone();
+                    page.proxy_host = served.proxy_host;
+                    page.proxy_port = served.proxy_port;
+                    false
+                }
+                None if served
+                    .iter()
+                    .any(|served| served.static_prefix == page.static_prefix) =>
+                {
+                    panic!(
+                        "spaxum: the page at {path_prefix} serves other assets from static prefix {}, \
+                        which is used by another page",
+                        page.static_prefix
+                    );
+                }
+                None => true,
+            };
+
+            let static_prefix = page.static_prefix.clone();
+
+            if serve_static {
+                served.push(ServedAssets {
+                    source,
+                    static_prefix: static_prefix.clone(),
+                    dev_state: page.dev_state.clone(),
+                    proxy_host: page.proxy_host,
+                    proxy_port: page.proxy_port,
+                });
+            }
+
+            let page_router: Router = page.router();
+
+            // the index.html refers to the assets by their absolute path, not below the page prefix
+            if serve_static {
+                router = router
+                    .route_service(&format!("{static_prefix}/{{*path}}"), page_router.clone());
+            }
+
+            router = if path_prefix == "/" {
+                router.fallback_service(page_router)
+            } else {
+                router.nest_service(&path_prefix, page_router)
+            };
+        }
+
+        router
+    }
+}
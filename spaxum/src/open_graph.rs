@@ -0,0 +1,201 @@
+use memory_serve::Asset;
+use std::path::Path;
+
+use crate::MetaKey;
+
+/// Open Graph and Twitter card meta tags of the index.html, for link previews, see `Spaxum::set_open_graph`
+#[derive(Debug, Clone, Default)]
+pub struct OpenGraph {
+    title: Option<String>,
+    description: Option<String>,
+    image_url: Option<String>,
+    url: Option<String>,
+    site_name: Option<String>,
+    og_type: Option<String>,
+    twitter_card: Option<String>,
+    twitter_site: Option<String>,
+}
+
+impl OpenGraph {
+    /// Create the meta tags, the title defaults to the page title
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the title of the preview
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+
+        self
+    }
+
+    /// Set the description of the preview
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+
+        self
+    }
+
+    /// Set the image of the preview, a URL or the file name of a bundled asset, e.g. `preview.png`
+    /// The file name is resolved to the path the asset is served from, including a content hash
+    pub fn image_url(mut self, image_url: impl Into<String>) -> Self {
+        self.image_url = Some(image_url.into());
+
+        self
+    }
+
+    /// Set the canonical URL of the page, image paths are made absolute with its origin
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+
+        self
+    }
+
+    /// Set the name of the site
+    pub fn site_name(mut self, site_name: impl Into<String>) -> Self {
+        self.site_name = Some(site_name.into());
+
+        self
+    }
+
+    /// Set the `og:type`, defaults to `website`
+    pub fn og_type(mut self, og_type: impl Into<String>) -> Self {
+        self.og_type = Some(og_type.into());
+
+        self
+    }
+
+    /// Set the `twitter:card`, defaults to `summary_large_image` with an image and `summary` without
+    pub fn twitter_card(mut self, twitter_card: impl Into<String>) -> Self {
+        self.twitter_card = Some(twitter_card.into());
+
+        self
+    }
+
+    /// Set the `twitter:site` account, e.g. `@example`
+    pub fn twitter_site(mut self, twitter_site: impl Into<String>) -> Self {
+        self.twitter_site = Some(twitter_site.into());
+
+        self
+    }
+
+    /// Meta tags of the preview, the image is resolved against the assets and the asset prefix
+    pub(crate) fn meta_tags(
+        &self,
+        page_title: &str,
+        assets: &[Asset],
+        asset_prefix: &str,
+    ) -> Vec<(MetaKey, String)> {
+        let title = self.title.as_deref().unwrap_or(page_title);
+        let image = self
+            .image_url
+            .as_deref()
+            .map(|image_url| self.resolve_image(image_url, assets, asset_prefix));
+
+        let property =
+            |name: &str, content: &str| (MetaKey::Property(name.to_string()), content.to_string());
+        let name =
+            |name: &str, content: &str| (MetaKey::Name(name.to_string()), content.to_string());
+
+        let twitter_card = match (&self.twitter_card, &image) {
+            (Some(twitter_card), _) => twitter_card.as_str(),
+            (None, Some(_)) => "summary_large_image",
+            (None, None) => "summary",
+        };
+
+        let mut tags = vec![
+            property("og:type", self.og_type.as_deref().unwrap_or("website")),
+            property("og:title", title),
+        ];
+
+        if let Some(description) = &self.description {
+            tags.push(property("og:description", description));
+        }
+        if let Some(url) = &self.url {
+            tags.push(property("og:url", url));
+        }
+        if let Some(site_name) = &self.site_name {
+            tags.push(property("og:site_name", site_name));
+        }
+        if let Some(image) = &image {
+            tags.push(property("og:image", image));
+        }
+
+        tags.push(name("twitter:card", twitter_card));
+        tags.push(name("twitter:title", title));
+
+        if let Some(description) = &self.description {
+            tags.push(name("twitter:description", description));
+        }
+        if let Some(image) = &image {
+            tags.push(name("twitter:image", image));
+        }
+        if let Some(twitter_site) = &self.twitter_site {
+            tags.push(name("twitter:site", twitter_site));
+        }
+
+        tags
+    }
+
+    /// Resolve the file name of a bundled asset to its path, and make paths absolute with the origin of the URL
+    fn resolve_image(&self, image_url: &str, assets: &[Asset], asset_prefix: &str) -> String {
+        if image_url.contains("://") {
+            return image_url.to_string();
+        }
+
+        let path = if image_url.starts_with('/') {
+            image_url.to_string()
+        } else {
+            let route = assets
+                .iter()
+                .map(|asset| asset.route)
+                .find(|route| is_asset_named(route, image_url))
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("/{image_url}"));
+
+            format!("{asset_prefix}{route}")
+        };
+
+        // the asset prefix can be a CDN URL
+        if path.contains("://") {
+            return path;
+        }
+
+        match self.url.as_deref().and_then(origin) {
+            Some(origin) => format!("{origin}{path}"),
+            None => path,
+        }
+    }
+}
+
+/// Check if the route of an asset is the file name, with or without a content hash, e.g. `preview-5QHS2WJN.png`
+fn is_asset_named(route: &str, file_name: &str) -> bool {
+    let route = route.trim_start_matches('/');
+    if route == file_name {
+        return true;
+    }
+
+    let (route, name) = (Path::new(route), Path::new(file_name));
+    let (Some(route_stem), Some(stem)) = (
+        route.file_stem().and_then(|stem| stem.to_str()),
+        name.file_stem().and_then(|stem| stem.to_str()),
+    ) else {
+        return false;
+    };
+
+    route.extension() == name.extension()
+        && route.parent() == name.parent()
+        && route_stem
+            .rsplit_once('-')
+            .is_some_and(|(route_stem, _)| route_stem == stem)
+}
+
+/// Origin of a URL, e.g. `https://example.com` of `https://example.com/app`
+fn origin(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("://")?;
+    let end = rest
+        .find('/')
+        .map_or(url.len(), |end| url.len() - rest.len() + end);
+
+    Some(&url[..end])
+}
@@ -0,0 +1,934 @@
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, OriginalUri, Request, State},
+    http::{
+        HeaderMap, HeaderName, HeaderValue, Method,
+        header::{
+            ACCEPT, CACHE_CONTROL, CONNECTION, CONTENT_TYPE, ETAG, HOST, IF_MATCH,
+            IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, IF_UNMODIFIED_SINCE, LAST_MODIFIED,
+            LOCATION, RANGE, RETRY_AFTER, TE, TRAILER, TRANSFER_ENCODING, UPGRADE,
+        },
+    },
+    response::{IntoResponse, Response},
+};
+use hyper::{StatusCode, Uri, body::Incoming};
+use hyper_util::{
+    client::legacy::connect::HttpConnector,
+    rt::{TokioExecutor, TokioIo},
+};
+use std::{
+    env,
+    error::Error,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::time::Instant;
+use tower_service::Service;
+
+use crate::dev::{DevState, ESBUILD_READY_TIMEOUT};
+
+/// Default host the esbuild dev server listens on
+pub(crate) const ESBUILD_DEV_HOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+/// Default port for the esbuild dev server
+pub(crate) const ESBUILD_DEV_PORT: u16 = 8888;
+
+/// Forwarding headers added to proxied requests
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+const X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
+const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+
+/// Disables response buffering by reverse proxies, like nginx
+const X_ACCEL_BUFFERING: HeaderName = HeaderName::from_static("x-accel-buffering");
+
+/// Hop-by-hop headers without a constant in `http`
+const KEEP_ALIVE: HeaderName = HeaderName::from_static("keep-alive");
+const PROXY_CONNECTION: HeaderName = HeaderName::from_static("proxy-connection");
+
+/// Default time to wait for the dev server to respond, finite so a hung esbuild does not keep requests open
+const PROXY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default time to retry requests for the entry assets while esbuild finishes its first build
+const PROXY_RETRY_BUDGET: Duration = Duration::from_secs(5);
+
+/// Delay between retries of a request for an entry asset
+const PROXY_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Timeouts of the dev proxy
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ProxyTimeouts {
+    /// Time to wait for the response headers of the dev server
+    pub(crate) response: Duration,
+    /// Time to wait for a connection to the dev server, no timeout if `None`
+    pub(crate) connect: Option<Duration>,
+    /// Time to retry requests for the entry assets that fail to connect or respond with 404
+    pub(crate) retry: Duration,
+}
+
+impl Default for ProxyTimeouts {
+    fn default() -> Self {
+        Self {
+            response: PROXY_TIMEOUT,
+            connect: None,
+            retry: PROXY_RETRY_BUDGET,
+        }
+    }
+}
+
+/// Connector of the dev proxy, plain HTTP unless the `tls` feature is enabled
+#[cfg(not(feature = "tls"))]
+type Connector = HttpConnector;
+#[cfg(feature = "tls")]
+type Connector = crate::tls::HttpsConnector;
+
+/// HTTP client to proxy request in development
+type Client = hyper_util::client::legacy::Client<Connector, Body>;
+
+/// Create a connector for plain HTTP connections
+fn http_connector(connect_timeout: Option<Duration>) -> HttpConnector {
+    let mut connector = HttpConnector::new();
+    connector.set_connect_timeout(connect_timeout);
+
+    connector
+}
+
+/// Create the HTTP client using the given connector
+fn client(connector: Connector) -> Client {
+    hyper_util::client::legacy::Client::<(), ()>::builder(TokioExecutor::new()).build(connector)
+}
+
+/// State of the development proxy, the HTTP client, the dev server address and the state of the esbuild dev server
+#[derive(Clone)]
+pub(crate) struct ProxyState {
+    client: Client,
+    connector: Connector,
+    scheme: &'static str,
+    authority: String,
+    base_path: String,
+    timeout: Duration,
+    retry_budget: Duration,
+    retry_paths: Arc<Vec<String>>,
+    forward_headers: Option<Arc<Vec<HeaderName>>>,
+    no_cache: bool,
+    dev: Arc<DevState>,
+}
+
+impl ProxyState {
+    /// Create the proxy state for an esbuild dev server at the given address
+    pub(crate) fn new(addr: SocketAddr, timeouts: ProxyTimeouts, dev: Arc<DevState>) -> Self {
+        Self::external(addr.to_string(), String::new(), timeouts, dev)
+    }
+
+    /// Create the proxy state for a dev server at the given authority (`host:port`) and base path
+    pub(crate) fn external(
+        authority: String,
+        base_path: String,
+        timeouts: ProxyTimeouts,
+        dev: Arc<DevState>,
+    ) -> Self {
+        #[cfg(not(feature = "tls"))]
+        let connector = http_connector(timeouts.connect);
+        #[cfg(feature = "tls")]
+        let connector = crate::tls::https_connector(http_connector(timeouts.connect), None);
+
+        let timeout = timeouts.response;
+        let retry_budget = timeouts.retry;
+
+        Self {
+            client: client(connector.clone()),
+            connector,
+            scheme: "http",
+            authority,
+            base_path,
+            timeout,
+            retry_budget,
+            retry_paths: Arc::new(Vec::new()),
+            forward_headers: None,
+            no_cache: false,
+            dev,
+        }
+    }
+
+    /// Connect to the dev server over HTTPS, only accepting the given certificate (PEM)
+    #[cfg(feature = "tls")]
+    pub(crate) fn with_tls(mut self, cert: &std::path::Path, timeouts: ProxyTimeouts) -> Self {
+        self.connector = crate::tls::https_connector(http_connector(timeouts.connect), Some(cert));
+        self.client = client(self.connector.clone());
+        self.scheme = "https";
+
+        self
+    }
+
+    /// Retry GET requests for the given paths, the entry assets that are only written after the first build
+    pub(crate) fn with_retry_paths(mut self, retry_paths: Vec<String>) -> Self {
+        self.retry_paths = Arc::new(retry_paths);
+
+        self
+    }
+
+    /// Prevent browsers from caching the assets between rebuilds, the validators of the dev server are removed
+    pub(crate) fn with_no_cache(mut self, no_cache: bool) -> Self {
+        self.no_cache = no_cache;
+
+        self
+    }
+
+    /// Only forward the given request headers to the dev server, instead of all headers
+    pub(crate) fn with_forward_headers(mut self, forward_headers: Option<Vec<HeaderName>>) -> Self {
+        self.forward_headers = forward_headers.map(Arc::new);
+
+        self
+    }
+}
+
+/// Parse the URL of an external dev server into its authority (`host:port`) and base path
+/// Only plain HTTP is supported, the port defaults to 80
+pub(crate) fn parse_dev_server_url(url: &str) -> Option<(String, String)> {
+    let uri = Uri::try_from(url).ok()?;
+
+    if uri.scheme_str() != Some("http") {
+        return None;
+    }
+
+    let authority = format!("{}:{}", uri.host()?, uri.port_u16().unwrap_or(80));
+    let base_path = uri.path().trim_end_matches('/').to_string();
+
+    Some((authority, base_path))
+}
+
+/// Error response of the dev proxy, a status code with a plain text diagnostic
+type ProxyError = Response;
+
+/// The request could not be forwarded to the dev server
+fn bad_request(reason: &str) -> ProxyError {
+    (StatusCode::BAD_REQUEST, format!("spaxum: {reason}\n")).into_response()
+}
+
+/// The dev server is still starting, ask the browser to retry
+fn service_unavailable(authority: &str) -> ProxyError {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(RETRY_AFTER, "1")],
+        format!("spaxum: the dev server at {authority} is starting, retry in a moment\n"),
+    )
+        .into_response()
+}
+
+/// The dev server could not be reached, log the error and respond with 502
+fn bad_gateway(authority: &str, error: &(dyn Error + 'static)) -> ProxyError {
+    // the error messages of hyper are terse, include the underlying causes
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {cause}"));
+        source = cause.source();
+    }
+
+    eprintln!("spaxum: dev server at {authority} is unreachable: {message}");
+
+    (
+        StatusCode::BAD_GATEWAY,
+        format!("spaxum: unable to reach the dev server at {authority}\n{message}\n"),
+    )
+        .into_response()
+}
+
+/// The dev server did not respond in time, log the error and respond with 504
+fn gateway_timeout(authority: &str, timeout: Duration) -> ProxyError {
+    eprintln!("spaxum: dev server at {authority} did not respond within {timeout:?}");
+
+    (
+        StatusCode::GATEWAY_TIMEOUT,
+        format!("spaxum: the dev server at {authority} did not respond within {timeout:?}\n"),
+    )
+        .into_response()
+}
+
+/// Read the esbuild dev server port from the `SPAXUM_DEV_PORT` environment variable
+/// Falls back to the default port if the variable is not set or invalid
+pub(crate) fn dev_port_from_env() -> u16 {
+    let Ok(port) = env::var("SPAXUM_DEV_PORT") else {
+        return ESBUILD_DEV_PORT;
+    };
+
+    match port.parse() {
+        Ok(port) => port,
+        Err(_) => {
+            eprintln!("spaxum: invalid SPAXUM_DEV_PORT \"{port}\", using {ESBUILD_DEV_PORT}");
+            ESBUILD_DEV_PORT
+        }
+    }
+}
+
+/// Proxy handler for development mode, proxies requests to the esbuild or external dev server
+/// Requests are forwarded with their original method and body
+pub(crate) async fn proxy_handler(
+    State(ProxyState {
+        client,
+        connector,
+        scheme,
+        authority,
+        base_path,
+        timeout,
+        retry_budget,
+        retry_paths,
+        forward_headers,
+        no_cache,
+        dev,
+    }): State<ProxyState>,
+    mut req: Request,
+) -> Result<Response, ProxyError> {
+    // right after startup or a restart esbuild is not listening yet
+    if !dev.wait_ready(ESBUILD_READY_TIMEOUT).await {
+        return Err(service_unavailable(&authority));
+    }
+
+    let path = req.uri().path();
+    let source_map = path.ends_with(".map");
+
+    // the path the static router is nested under, to rewrite the redirects of the dev server
+    let prefix = req
+        .extensions()
+        .get::<OriginalUri>()
+        .and_then(|OriginalUri(original_uri)| original_uri.path().strip_suffix(path))
+        .unwrap_or_default()
+        .to_string();
+
+    // the entry assets 404 until esbuild finished its first build
+    let retry = req.method() == Method::GET
+        && !retry_budget.is_zero()
+        && retry_paths.iter().any(|retry_path| retry_path == path);
+
+    let path_query = req
+        .uri()
+        .path_and_query()
+        .map(|v| v.as_str())
+        .unwrap_or(path);
+
+    let uri = format!("{scheme}://{authority}{base_path}{path_query}");
+
+    let Ok(uri) = Uri::try_from(uri) else {
+        return Err(bad_request("invalid request uri"));
+    };
+
+    *req.uri_mut() = uri;
+
+    if let Some(forward_headers) = forward_headers {
+        retain_forward_headers(req.headers_mut(), &forward_headers);
+    }
+
+    set_forwarded_headers(&mut req);
+
+    // esbuild validates the Host header, so rewrite it for the internal proxy hop.
+    let Ok(host) = HeaderValue::from_str(&authority) else {
+        return Err(bad_request("invalid dev server address"));
+    };
+    req.headers_mut().insert(HOST, host);
+
+    // the upgrade headers are hop-by-hop, but they are needed to open the websocket tunnel
+    if is_websocket_upgrade(&req) {
+        return proxy_upgrade(connector, &authority, req).await;
+    }
+
+    remove_hop_by_hop_headers(req.headers_mut());
+
+    // event streams, like the esbuild live reload stream, are long-lived and never timed out
+    let response = if is_event_stream(&req) {
+        client.request(req).await
+    } else if retry {
+        match tokio::time::timeout(timeout, request_with_retry(&client, req, retry_budget)).await {
+            Ok(response) => response,
+            Err(_) => return Err(gateway_timeout(&authority, timeout)),
+        }
+    } else {
+        match tokio::time::timeout(timeout, client.request(req)).await {
+            Ok(response) => response,
+            Err(_) => return Err(gateway_timeout(&authority, timeout)),
+        }
+    };
+
+    match response {
+        // the upstream body is streamed to the client as it arrives, when the client disconnects
+        // the body is dropped which closes the upstream connection, the server determines the
+        // framing of the client connection itself
+        Ok(mut response) => {
+            // end-to-end headers, like `SourceMap`, `Content-Range` and `Accept-Ranges`, are passed on
+            // as is, so a `206 Partial Content` response reaches the client unchanged
+            remove_hop_by_hop_headers(response.headers_mut());
+            rewrite_location(
+                response.headers_mut(),
+                &format!("{scheme}://{authority}"),
+                &base_path,
+                &prefix,
+            );
+
+            // devtools only parse source maps served as JSON, a specific content type of the dev server is kept
+            if source_map && response.status().is_success() && has_generic_content_type(&response) {
+                response
+                    .headers_mut()
+                    .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            }
+
+            // every event is sent as a separate frame, tell proxies in front of spaxum to not buffer
+            // or cache them, so events reach the browser as soon as the dev server emits them
+            if is_event_stream_response(&response) {
+                let headers = response.headers_mut();
+                headers.insert(X_ACCEL_BUFFERING, HeaderValue::from_static("no"));
+                headers
+                    .entry(CACHE_CONTROL)
+                    .or_insert(HeaderValue::from_static("no-cache"));
+            } else if no_cache {
+                // without validators the browser cannot revalidate a stale asset, it always refetches
+                // partial responses keep them, `If-Range` needs them to continue a range of the same file
+                let partial = response.status() == StatusCode::PARTIAL_CONTENT;
+                let headers = response.headers_mut();
+                headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+                if !partial {
+                    headers.remove(ETAG);
+                    headers.remove(LAST_MODIFIED);
+                }
+            }
+
+            Ok(response.map(Body::new))
+        }
+        Err(e) => Err(bad_gateway(&authority, &e)),
+    }
+}
+
+/// Send a GET request, retrying connection errors and 404 responses until the retry budget is spent
+/// The request body is not sent, GET requests have no body
+async fn request_with_retry(
+    client: &Client,
+    req: Request,
+    budget: Duration,
+) -> Result<hyper::Response<Incoming>, hyper_util::client::legacy::Error> {
+    let (parts, _) = req.into_parts();
+    let deadline = Instant::now() + budget;
+
+    loop {
+        let response = client
+            .request(Request::from_parts(parts.clone(), Body::empty()))
+            .await;
+
+        let retry = match &response {
+            Ok(response) => response.status() == StatusCode::NOT_FOUND,
+            Err(_) => true,
+        };
+
+        if !retry || Instant::now() + PROXY_RETRY_DELAY > deadline {
+            return response;
+        }
+
+        tokio::time::sleep(PROXY_RETRY_DELAY).await;
+    }
+}
+
+/// Add the `X-Forwarded-For`, `X-Forwarded-Host` and `X-Forwarded-Proto` headers
+/// The client address is only known when the server is started with connect info
+fn set_forwarded_headers(req: &mut Request) {
+    let client_ip = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+
+    let headers = req.headers_mut();
+
+    if let Some(client_ip) = client_ip {
+        // append the client to the addresses of earlier proxies
+        let forwarded_for = match headers
+            .get(X_FORWARDED_FOR)
+            .and_then(|value| value.to_str().ok())
+        {
+            Some(forwarded_for) => format!("{forwarded_for}, {client_ip}"),
+            None => client_ip,
+        };
+
+        if let Ok(value) = HeaderValue::from_str(&forwarded_for) {
+            headers.insert(X_FORWARDED_FOR, value);
+        }
+    }
+
+    // a reverse proxy in front of the application sets the host the browser uses
+    if !headers.contains_key(X_FORWARDED_HOST)
+        && let Some(host) = headers.get(HOST).cloned()
+    {
+        headers.insert(X_FORWARDED_HOST, host);
+    }
+
+    // a TLS terminator in front of the application sets the protocol the browser uses
+    if !headers.contains_key(X_FORWARDED_PROTO) {
+        headers.insert(X_FORWARDED_PROTO, HeaderValue::from_static("http"));
+    }
+}
+
+/// Rewrite the redirects of the dev server to the path they are proxied from
+/// An absolute URL of the dev server, e.g. `http://127.0.0.1:8888/`, must not reach the browser, it is
+/// not reachable from other machines and it is mixed content when the application is served over HTTPS
+fn rewrite_location(headers: &mut HeaderMap, upstream: &str, base_path: &str, prefix: &str) {
+    let Some(location) = headers.get(LOCATION).and_then(|value| value.to_str().ok()) else {
+        return;
+    };
+
+    let upstream_path = match location.strip_prefix(upstream) {
+        Some(path) => path,
+        None if location.starts_with('/') && !location.starts_with("//") => location,
+        // redirects to other origins are passed on as is
+        None => return,
+    };
+
+    let Some(path) = upstream_path.strip_prefix(base_path) else {
+        return;
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&format!("{prefix}{path}")) {
+        headers.insert(LOCATION, value);
+    }
+}
+
+/// Range and conditional request headers, passed upstream untouched, e.g. to seek in a video
+const RANGE_HEADERS: [HeaderName; 6] = [
+    RANGE,
+    IF_RANGE,
+    IF_MATCH,
+    IF_NONE_MATCH,
+    IF_MODIFIED_SINCE,
+    IF_UNMODIFIED_SINCE,
+];
+
+/// Remove the request headers that are not in the allowlist
+/// The headers the proxy needs itself (`Host` and the websocket upgrade headers) and the range and
+/// conditional request headers are always kept
+fn retain_forward_headers(headers: &mut HeaderMap, forward_headers: &[HeaderName]) {
+    let removed = headers
+        .keys()
+        .filter(|name| {
+            !forward_headers.contains(name)
+                && !RANGE_HEADERS.contains(name)
+                && *name != HOST
+                && *name != CONNECTION
+                && *name != UPGRADE
+                && !name.as_str().starts_with("sec-websocket-")
+        })
+        .cloned()
+        .collect::<Vec<HeaderName>>();
+
+    for name in removed {
+        headers.remove(name);
+    }
+}
+
+/// Remove the hop-by-hop headers (RFC 7230, section 6.1), including the headers listed in `Connection`
+fn remove_hop_by_hop_headers(headers: &mut HeaderMap) {
+    let connection_headers = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|token| HeaderName::try_from(token.trim()).ok())
+        .collect::<Vec<HeaderName>>();
+
+    for name in connection_headers {
+        headers.remove(name);
+    }
+
+    for name in [
+        CONNECTION,
+        KEEP_ALIVE,
+        PROXY_CONNECTION,
+        TE,
+        TRAILER,
+        TRANSFER_ENCODING,
+        UPGRADE,
+    ] {
+        headers.remove(name);
+    }
+}
+
+/// Check if the request asks for a server-sent events stream (`Accept: text/event-stream`),
+/// or for the esbuild change events at `/esbuild`
+fn is_event_stream(req: &Request) -> bool {
+    let accept_event_stream = req
+        .headers()
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("text/event-stream"));
+
+    accept_event_stream || req.uri().path().ends_with("/esbuild")
+}
+
+/// Check if the response has no content type, or one that does not say what the content is
+fn has_generic_content_type<B>(response: &hyper::Response<B>) -> bool {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_none_or(|value| value.starts_with("application/octet-stream"))
+}
+
+/// Check if the response is a server-sent events stream
+fn is_event_stream_response<B>(response: &hyper::Response<B>) -> bool {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"))
+}
+
+/// Health check for development mode, checks that the esbuild or external dev server responds
+/// Returns 503 if the dev server is not running or does not respond in time,
+/// and "degraded" if esbuild had to be restarted after it exited
+pub(crate) async fn dev_health_handler(
+    State(ProxyState {
+        client,
+        scheme,
+        authority,
+        base_path,
+        timeout,
+        dev,
+        ..
+    }): State<ProxyState>,
+) -> (StatusCode, &'static str) {
+    let Ok(uri) = Uri::try_from(format!("{scheme}://{authority}{base_path}/")) else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "dev server unavailable");
+    };
+
+    let request = axum::http::Request::get(uri)
+        .body(Body::empty())
+        .expect("health check request is valid");
+
+    // any response means the dev server is alive, even an error status
+    match tokio::time::timeout(timeout, client.request(request)).await {
+        Ok(Ok(_)) if dev.restarts() > 0 => (StatusCode::OK, "degraded"),
+        Ok(Ok(_)) => (StatusCode::OK, "ok"),
+        _ => (StatusCode::SERVICE_UNAVAILABLE, "dev server unavailable"),
+    }
+}
+
+/// Check if the request asks for a websocket upgrade (`Connection: upgrade` and `Upgrade: websocket`)
+fn is_websocket_upgrade(req: &Request) -> bool {
+    let connection_upgrade = req
+        .headers()
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+
+    let upgrade_websocket = req
+        .headers()
+        .get(UPGRADE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+
+    connection_upgrade && upgrade_websocket
+}
+
+/// Box the connector error, the error type depends on the connector
+fn boxed_error(error: impl Into<Box<dyn Error + Send + Sync>>) -> Box<dyn Error + Send + Sync> {
+    error.into()
+}
+
+/// Tunnel a websocket upgrade to the dev server over a dedicated connection
+/// Once both sides are upgraded, data is copied in both directions until either side closes
+async fn proxy_upgrade(
+    mut connector: Connector,
+    authority: &str,
+    mut req: Request,
+) -> Result<Response, ProxyError> {
+    let client_upgrade = hyper::upgrade::on(&mut req);
+    let upstream_uri = req.uri().clone();
+
+    // the upstream connection is not pooled, so the request is sent in origin form
+    let path_query = req
+        .uri()
+        .path_and_query()
+        .map(|v| v.as_str())
+        .unwrap_or("/")
+        .to_string();
+
+    let Ok(uri) = Uri::try_from(path_query) else {
+        return Err(bad_request("invalid request uri"));
+    };
+
+    *req.uri_mut() = uri;
+
+    // the connector also sets up TLS when the dev server uses HTTPS
+    let stream = match std::future::poll_fn(|cx| connector.poll_ready(cx)).await {
+        Ok(()) => connector.call(upstream_uri).await,
+        Err(e) => Err(e),
+    }
+    .map_err(|e| bad_gateway(authority, &*boxed_error(e)))?;
+
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(stream)
+        .await
+        .map_err(|e| bad_gateway(authority, &e))?;
+
+    tokio::spawn(async move {
+        if let Err(e) = connection.with_upgrades().await {
+            eprintln!("spaxum: websocket upstream connection failed: {e}");
+        }
+    });
+
+    let mut upstream_response = sender
+        .send_request(req)
+        .await
+        .map_err(|e| bad_gateway(authority, &e))?;
+
+    // the upstream refused the upgrade, pass its response on to the client
+    if upstream_response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        return Ok(upstream_response.into_response());
+    }
+
+    let upstream_upgrade = hyper::upgrade::on(&mut upstream_response);
+
+    tokio::spawn(async move {
+        let (Ok(client_io), Ok(upstream_io)) = tokio::join!(client_upgrade, upstream_upgrade)
+        else {
+            eprintln!("spaxum: websocket upgrade failed");
+            return;
+        };
+
+        let mut client_io = TokioIo::new(client_io);
+        let mut upstream_io = TokioIo::new(upstream_io);
+
+        if let Err(e) = tokio::io::copy_bidirectional(&mut client_io, &mut upstream_io).await {
+            eprintln!("spaxum: websocket connection closed: {e}");
+        }
+    });
+
+    Ok(upstream_response.map(|_| Body::empty()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::{
+        Router,
+        body::{BodyDataStream, Bytes, to_bytes},
+        routing::get,
+    };
+    use std::{convert::Infallible, sync::Mutex};
+    use tokio::sync::mpsc;
+    use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+
+    /// Source map header of JavaScript responses
+    const SOURCE_MAP: HeaderName = HeaderName::from_static("sourcemap");
+
+    /// Serve the router on a free local port, returns the address of the upstream
+    pub(crate) async fn mock_upstream(router: Router) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .expect("unable to bind the mock upstream");
+        let addr = listener.local_addr().expect("mock upstream has no address");
+
+        tokio::spawn(async move {
+            axum::serve(listener, router)
+                .await
+                .expect("mock upstream failed");
+        });
+
+        addr
+    }
+
+    /// Router that proxies every request to the upstream
+    pub(crate) fn proxy_router(upstream: SocketAddr) -> Router {
+        let dev = Arc::new(DevState::default());
+        dev.set_ready(true);
+
+        Router::new()
+            .fallback(proxy_handler)
+            .with_state(ProxyState::new(upstream, ProxyTimeouts::default(), dev))
+    }
+
+    /// Send a GET request to the router
+    pub(crate) async fn get_response(router: &mut Router, uri: &str) -> Response {
+        let request = Request::get(uri)
+            .body(Body::empty())
+            .expect("invalid request");
+
+        match router.call(request).await {
+            Ok(response) => response,
+            Err(e) => match e {},
+        }
+    }
+
+    #[tokio::test]
+    async fn source_map_content_type() {
+        let upstream = mock_upstream(
+            Router::new()
+                .route(
+                    "/generic.js.map",
+                    get(|| async { ([(CONTENT_TYPE, "application/octet-stream")], "{}") }),
+                )
+                .route(
+                    "/specific.js.map",
+                    get(|| async { ([(CONTENT_TYPE, "application/json; charset=utf-8")], "{}") }),
+                )
+                .route(
+                    "/index.js",
+                    get(|| async {
+                        (
+                            [
+                                (CONTENT_TYPE, "text/javascript"),
+                                (SOURCE_MAP, "index.js.map"),
+                            ],
+                            "",
+                        )
+                    }),
+                ),
+        )
+        .await;
+        let mut router = proxy_router(upstream);
+
+        let response = get_response(&mut router, "/generic.js.map").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+
+        let response = get_response(&mut router, "/specific.js.map").await;
+        assert_eq!(
+            response.headers()[CONTENT_TYPE],
+            "application/json; charset=utf-8"
+        );
+
+        let response = get_response(&mut router, "/index.js").await;
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/javascript");
+        assert_eq!(response.headers()[SOURCE_MAP], "index.js.map");
+    }
+
+    #[tokio::test]
+    async fn missing_source_map() {
+        let upstream = mock_upstream(Router::new()).await;
+        let mut router: Router =
+            crate::Spaxum::new_external_proxy("Test", &format!("http://{upstream}")).router();
+
+        let response = get_response(&mut router, "/static/index.js.map").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("unable to read the body");
+        assert!(!String::from_utf8_lossy(&body).contains("<html"));
+    }
+
+    /// Upstream that serves `/stream` with the given content type, the body is sent through the returned channel
+    /// and ends when the sender is dropped
+    pub(crate) async fn streaming_upstream(
+        content_type: &'static str,
+    ) -> (SocketAddr, mpsc::Sender<Bytes>) {
+        let (sender, receiver) = mpsc::channel::<Bytes>(4);
+        let receiver = Arc::new(Mutex::new(Some(receiver)));
+
+        let upstream = mock_upstream(Router::new().route(
+            "/stream",
+            get(move || async move {
+                let receiver = receiver
+                    .lock()
+                    .expect("stream lock poisoned")
+                    .take()
+                    .expect("the stream is requested once");
+                let body = ReceiverStream::new(receiver).map(Ok::<_, Infallible>);
+
+                ([(CONTENT_TYPE, content_type)], Body::from_stream(body))
+            }),
+        ))
+        .await;
+
+        (upstream, sender)
+    }
+
+    /// Wait for the next chunk of the body, `None` when the body ended or nothing arrives within a second
+    pub(crate) async fn next_chunk(body: &mut BodyDataStream) -> Option<Bytes> {
+        tokio::time::timeout(Duration::from_secs(1), body.next())
+            .await
+            .ok()
+            .flatten()
+            .map(|chunk| chunk.expect("body failed"))
+    }
+
+    #[tokio::test]
+    async fn streams_large_body() {
+        /// Size of the proxied body, large enough that buffering it would show in the memory usage
+        const SIZE: usize = 100 * 1024 * 1024;
+        const CHUNK: usize = 1024 * 1024;
+
+        let (upstream, sender) = streaming_upstream("video/mp4").await;
+        let mut router = proxy_router(upstream);
+
+        sender.send(Bytes::from(vec![0; CHUNK])).await.unwrap();
+
+        let response = get_response(&mut router, "/stream").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = response.into_body().into_data_stream();
+
+        // the first chunk arrives while the upstream has not finished the body
+        let first = next_chunk(&mut body)
+            .await
+            .expect("first chunk not streamed");
+        let mut received = first.len();
+
+        let producer = tokio::spawn(async move {
+            for _ in 1..SIZE / CHUNK {
+                sender.send(Bytes::from(vec![0; CHUNK])).await.unwrap();
+            }
+        });
+
+        // at most a few chunks are in flight, the body is never held as a whole
+        while let Some(chunk) = next_chunk(&mut body).await {
+            assert!(chunk.len() <= CHUNK);
+            received += chunk.len();
+        }
+
+        producer.await.unwrap();
+        assert_eq!(received, SIZE);
+    }
+
+    #[tokio::test]
+    async fn streams_event_stream() {
+        let (upstream, sender) = streaming_upstream("text/event-stream").await;
+        let mut router = proxy_router(upstream);
+
+        let response = get_response(&mut router, "/stream").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/event-stream");
+        assert_eq!(response.headers()[X_ACCEL_BUFFERING], "no");
+        assert_eq!(response.headers()[CACHE_CONTROL], "no-cache");
+        let mut body = response.into_body().into_data_stream();
+
+        // every event arrives on its own, while the upstream keeps the stream open
+        for event in [
+            "event: change\ndata: one\n\n",
+            "event: change\ndata: two\n\n",
+        ] {
+            sender.send(Bytes::from(event)).await.unwrap();
+
+            let chunk = next_chunk(&mut body).await.expect("event not streamed");
+            assert_eq!(chunk, event);
+        }
+
+        drop(sender);
+        assert_eq!(next_chunk(&mut body).await, None);
+    }
+
+    #[test]
+    fn keeps_forwarded_host_and_proto() {
+        let mut req = Request::get("/")
+            .header(HOST, "127.0.0.1:3000")
+            .body(Body::empty())
+            .unwrap();
+        set_forwarded_headers(&mut req);
+        assert_eq!(req.headers()[X_FORWARDED_HOST], "127.0.0.1:3000");
+        assert_eq!(req.headers()[X_FORWARDED_PROTO], "http");
+
+        let mut req = Request::get("/")
+            .header(HOST, "127.0.0.1:3000")
+            .header(X_FORWARDED_HOST, "example.com")
+            .header(X_FORWARDED_PROTO, "https")
+            .body(Body::empty())
+            .unwrap();
+        set_forwarded_headers(&mut req);
+        assert_eq!(req.headers()[X_FORWARDED_HOST], "example.com");
+        assert_eq!(req.headers()[X_FORWARDED_PROTO], "https");
+    }
+}
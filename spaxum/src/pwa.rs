@@ -0,0 +1,60 @@
+use serde::Serialize;
+
+/// Path the web app manifest is served from
+pub(crate) const PWA_MANIFEST_ROUTE: &str = "/manifest.json";
+
+/// Web app manifest of a Progressive Web App, see `Spaxum::with_pwa_manifest`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PwaManifest {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display: Option<PwaDisplay>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme_color: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub icons: Vec<PwaIcon>,
+}
+
+/// Display mode of a Progressive Web App
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PwaDisplay {
+    Fullscreen,
+    Standalone,
+    MinimalUi,
+    Browser,
+}
+
+/// Icon of a Progressive Web App
+/// A relative `src` is a file served from the static prefix, include it with `include_pwa_icons` in the build script
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PwaIcon {
+    pub src: String,
+    /// Space separated sizes, e.g. `192x192`
+    pub sizes: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+}
+
+impl PwaManifest {
+    /// Serialize the manifest, relative icon paths are resolved against the static prefix
+    pub(crate) fn to_json(&self, static_prefix: &str) -> String {
+        let mut manifest = self.clone();
+
+        for icon in &mut manifest.icons {
+            if !icon.src.starts_with('/') && !icon.src.contains("://") {
+                icon.src = format!("{static_prefix}/{}", icon.src);
+            }
+        }
+
+        serde_json::to_string(&manifest).unwrap_or_default()
+    }
+}
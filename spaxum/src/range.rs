@@ -0,0 +1,266 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::{
+        HeaderValue, StatusCode,
+        header::{
+            ACCEPT_ENCODING, ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, ETAG,
+            IF_RANGE, RANGE,
+        },
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::ops::Range;
+
+/// Serve single byte ranges of the assets served from memory, e.g. to resume the download of a large WASM module
+/// The asset is requested without compression and sliced, multiple ranges are answered with the whole asset
+pub(crate) async fn range_handler(mut req: Request, next: Next) -> Response {
+    let Some(range) = req
+        .headers()
+        .get(RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_range)
+    else {
+        let mut response = next.run(req).await;
+        if response.status() == StatusCode::OK {
+            response
+                .headers_mut()
+                .insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        }
+
+        return response;
+    };
+
+    let if_range = req.headers().get(IF_RANGE).cloned();
+
+    // the range applies to the uncompressed asset
+    req.headers_mut().remove(RANGE);
+    req.headers_mut().remove(ACCEPT_ENCODING);
+
+    let response = next.run(req).await;
+
+    // a changed asset is sent as a whole, the client cannot combine the range with what it has
+    let unchanged = if_range.is_none_or(|if_range| response.headers().get(ETAG) == Some(&if_range));
+
+    if response.status() != StatusCode::OK
+        || !unchanged
+        || response.headers().contains_key(CONTENT_ENCODING)
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    let length = bytes.len();
+
+    let Some(range) = range.resolve(length) else {
+        return (
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            [(CONTENT_RANGE, format!("bytes */{length}"))],
+        )
+            .into_response();
+    };
+
+    let content_range = format!("bytes {}-{}/{length}", range.start, range.end - 1);
+    let partial = bytes.slice(range);
+
+    parts.status = StatusCode::PARTIAL_CONTENT;
+    parts
+        .headers
+        .insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    parts
+        .headers
+        .insert(CONTENT_LENGTH, HeaderValue::from(partial.len()));
+    if let Ok(content_range) = HeaderValue::try_from(content_range) {
+        parts.headers.insert(CONTENT_RANGE, content_range);
+    }
+
+    Response::from_parts(parts, Body::from(partial))
+}
+
+/// Byte range of a `Range` header, before the length of the asset is known
+#[derive(Debug, Clone, Copy)]
+enum ByteRange {
+    /// `bytes=start-` or `bytes=start-end`, the end is inclusive
+    From(usize, Option<usize>),
+    /// `bytes=-length`, the last bytes
+    Suffix(usize),
+}
+
+impl ByteRange {
+    /// Resolve the range for the length of the asset, `None` if the range is not satisfiable
+    fn resolve(self, length: usize) -> Option<Range<usize>> {
+        match self {
+            ByteRange::From(start, _) if start >= length => None,
+            ByteRange::From(start, end) => {
+                let end = end.map_or(length, |end| end.saturating_add(1).min(length));
+                Some(start..end)
+            }
+            ByteRange::Suffix(suffix) if suffix == 0 || length == 0 => None,
+            ByteRange::Suffix(suffix) => Some(length.saturating_sub(suffix)..length),
+        }
+    }
+}
+
+/// Parse a `Range` header with a single byte range, other ranges are ignored
+fn parse_range(value: &str) -> Option<ByteRange> {
+    let range = value.trim().strip_prefix("bytes=")?;
+
+    if range.contains(',') {
+        return None;
+    }
+
+    let (start, end) = range.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        return Some(ByteRange::Suffix(end.parse().ok()?));
+    }
+
+    let start = start.parse().ok()?;
+    let end = match end {
+        "" => None,
+        end => Some(end.parse().ok()?),
+    };
+
+    // an invalid range is ignored, the whole asset is sent
+    if end.is_some_and(|end| end < start) {
+        return None;
+    }
+
+    Some(ByteRange::From(start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, middleware::from_fn, routing::get};
+    use tower_service::Service;
+
+    /// Asset of 1000 bytes, the byte at each offset is the offset modulo 256
+    fn asset() -> Vec<u8> {
+        (0..1000).map(|offset| (offset % 256) as u8).collect()
+    }
+
+    /// Router that serves the asset with an ETag, with range support
+    fn asset_router() -> Router {
+        Router::new()
+            .route(
+                "/asset.bin",
+                get(|| async { ([(ETAG, "\"asset\"")], asset()) }),
+            )
+            .layer(from_fn(range_handler))
+    }
+
+    /// Request the asset with the given request headers
+    async fn request(router: &mut Router, headers: &[(&str, &str)]) -> Response {
+        let mut request = Request::get("/asset.bin");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        match router
+            .call(request.body(Body::empty()).expect("invalid request"))
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => match e {},
+        }
+    }
+
+    #[test]
+    fn parse_single_range() {
+        let range = parse_range("bytes=100-199").unwrap();
+        assert_eq!(range.resolve(1000), Some(100..200));
+        // the end is clamped to the length
+        assert_eq!(range.resolve(150), Some(100..150));
+    }
+
+    #[test]
+    fn parse_suffix_range() {
+        let range = parse_range("bytes=-100").unwrap();
+        assert_eq!(range.resolve(1000), Some(900..1000));
+        assert_eq!(range.resolve(50), Some(0..50));
+        assert_eq!(parse_range("bytes=-0").unwrap().resolve(1000), None);
+    }
+
+    #[test]
+    fn parse_open_ended_range() {
+        let range = parse_range("bytes=900-").unwrap();
+        assert_eq!(range.resolve(1000), Some(900..1000));
+    }
+
+    #[test]
+    fn parse_invalid_ranges() {
+        assert!(parse_range("bytes=200-100").is_none());
+        assert!(parse_range("bytes=0-1,5-6").is_none());
+        assert!(parse_range("items=0-1").is_none());
+        assert!(parse_range("bytes=a-b").is_none());
+    }
+
+    #[test]
+    fn unsatisfiable_range() {
+        assert_eq!(parse_range("bytes=1000-").unwrap().resolve(1000), None);
+    }
+
+    #[tokio::test]
+    async fn range_response() {
+        let response = request(&mut asset_router(), &[("range", "bytes=100-199")]).await;
+
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[CONTENT_RANGE], "bytes 100-199/1000");
+        assert_eq!(response.headers()[CONTENT_LENGTH], "100");
+        assert_eq!(response.headers()[ACCEPT_RANGES], "bytes");
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), &asset()[100..200]);
+    }
+
+    #[tokio::test]
+    async fn unsatisfiable_range_response() {
+        let response = request(&mut asset_router(), &[("range", "bytes=2000-")]).await;
+
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[CONTENT_RANGE], "bytes */1000");
+    }
+
+    #[tokio::test]
+    async fn if_range_mismatch() {
+        let mut router = asset_router();
+
+        let response = request(
+            &mut router,
+            &[("range", "bytes=100-199"), ("if-range", "\"other\"")],
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.len(), 1000);
+
+        let response = request(
+            &mut router,
+            &[("range", "bytes=100-199"), ("if-range", "\"asset\"")],
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn range_through_proxy() {
+        let upstream = crate::proxy::tests::mock_upstream(asset_router()).await;
+        let mut router = crate::proxy::tests::proxy_router(upstream);
+
+        let response = request(&mut router, &[("range", "bytes=100-199")]).await;
+
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[CONTENT_RANGE], "bytes 100-199/1000");
+        assert_eq!(response.headers()[ACCEPT_RANGES], "bytes");
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), &asset()[100..200]);
+    }
+}
@@ -0,0 +1,110 @@
+use axum::{
+    extract::State,
+    http::{
+        StatusCode, Uri,
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+    },
+    response::{IntoResponse, Response},
+};
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::sync::watch;
+
+use crate::{
+    asset_manifest::content_type,
+    dev::{DevState, shutdown_requested},
+};
+
+/// Interval at which the dist directory is checked for a new build
+const DIST_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Size and modification time of the files in the dist directory
+type DistSnapshot = Vec<(PathBuf, u64, Option<SystemTime>)>;
+
+/// Serve a file from the esbuild dist directory, used when esbuild runs without its dev server
+/// Files are never cached, so the browser always loads the latest build
+pub(crate) async fn dist_file_handler(State(dist_dir): State<Arc<PathBuf>>, uri: Uri) -> Response {
+    let Some(path) = dist_path(&dist_dir, uri.path()) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match tokio::fs::read(&path).await {
+        Ok(contents) => (
+            [
+                (CONTENT_TYPE, content_type(&path)),
+                (CACHE_CONTROL, "no-cache"),
+            ],
+            contents,
+        )
+            .into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Resolve a request path to a file in the dist directory
+/// Returns `None` for paths that could escape the dist directory
+fn dist_path(dist_dir: &Path, request_path: &str) -> Option<PathBuf> {
+    let relative = Path::new(request_path.trim_start_matches('/'));
+
+    if relative.as_os_str().is_empty()
+        || !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    Some(dist_dir.join(relative))
+}
+
+/// Watch the dist directory and reload the browsers after esbuild wrote a new build
+/// A change is reported once the directory did not change for an interval, so a build that writes
+/// several files reloads once, this does not depend on the esbuild log level
+pub(crate) async fn watch_dist_dir(
+    dist_dir: PathBuf,
+    dev_state: Arc<DevState>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut snapshot = dist_snapshot(dist_dir.clone()).await;
+    let mut changed = false;
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(DIST_POLL_INTERVAL) => {},
+            _ = shutdown_requested(&mut shutdown) => return,
+        }
+
+        let next = dist_snapshot(dist_dir.clone()).await;
+
+        if next != snapshot {
+            snapshot = next;
+            changed = true;
+        } else if changed {
+            changed = false;
+            dev_state.dist_changed();
+        }
+    }
+}
+
+/// Read the size and modification time of every file in the dist directory
+async fn dist_snapshot(dist_dir: PathBuf) -> DistSnapshot {
+    tokio::task::spawn_blocking(move || {
+        let mut files = Vec::new();
+        crate::bundle::output_files(&dist_dir, &mut files);
+        files.sort();
+
+        files
+            .into_iter()
+            .filter_map(|path| {
+                let metadata = std::fs::metadata(&path).ok()?;
+
+                Some((path, metadata.len(), metadata.modified().ok()))
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}